@@ -0,0 +1,17 @@
+use cosmwasm_std::from_slice;
+use secret_toolkit_crypto::secp256k1::PrivateKey;
+
+use crate::ecies;
+use crate::error::ContractError;
+use crate::msg::{EncryptedResponse, StrongboxResponse};
+
+/// Decrypts a `GetStrongbox` response requested with `encrypt_to` set to the public key of
+/// `private_key`
+pub fn decrypt_strongbox(
+    private_key: &[u8; 32],
+    encrypted: &EncryptedResponse,
+) -> Result<StrongboxResponse, ContractError> {
+    let private_key =
+        PrivateKey::parse(private_key).map_err(|_| ContractError::InvalidPrivateKey)?;
+    Ok(from_slice(&ecies::decrypt(&private_key, encrypted)?)?)
+}
@@ -1,260 +1,5871 @@
 use base64::engine::{general_purpose, Engine};
 use cosmwasm_std::{
-    entry_point, to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError,
-    StdResult,
+    entry_point, from_binary, to_binary, to_vec, Addr, Binary, BlockInfo, CanonicalAddr, Deps,
+    DepsMut, Env, MessageInfo, Reply, Response, StdResult, Storage, SubMsg, WasmMsg,
 };
-use secret_toolkit_crypto::sha_256;
+use secret_toolkit::permit::{validate, Permit, RevokedPermits};
+use secret_toolkit_crypto::secp256k1::{PrivateKey, PublicKey};
+use secret_toolkit_crypto::{sha_256, Prng};
 
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, StrongboxResponse};
+use crate::ecies::{self, NONCE_SIZE};
+use crate::error::ContractError;
+use crate::msg::{
+    EncryptedResponse, EntryLabelsResponse, EntryResponse, ExecuteAnswer, ExecuteMsg,
+    GuardiansInfo, GuardiansResponse, InstantiateMsg, MigrateMsg, PendingOwnerResponse, QueryMsg,
+    QueryWithPermit, RecoveryInfo, RecoveryResponse, ResponseStatus, RevisionInfo,
+    RevisionResponse, RevisionsResponse, RoleInfo, RolesResponse, StrongboxPermission,
+    StrongboxResponse, StrongboxesResponse, ViewerInfo, ViewersResponse, ViewingKeyResponse,
+};
 use crate::state::{
-    config, config_read, read_viewing_key, revoke_viewing_key, write_viewing_key, State,
-    ENTROPY_LEN, INITIAL_SEED_LEN,
+    beneficiary, beneficiary_read, clear_roles, config, config_read, contract_version,
+    contract_version_read, entry_labels, entry_labels_read, factory, factory_read, guardians,
+    guardians_read, has_entropy_hash, index_viewer, key_hash_secret_read, legacy_config_read,
+    legacy_state_fields_read, mode, mode_read, multisig, multisig_read, owner_activity,
+    owner_activity_read, pending_instance_owner, pending_instance_owner_read, proposal_count,
+    proposal_count_read, read_entry, read_owned_strongboxes, read_proposal, read_revision,
+    read_roles, read_viewer, read_viewer_grant, read_viewing_key, recovery, recovery_read,
+    remove_entry, remove_proposal, remove_revision, remove_viewer_grant, revoke_viewing_key,
+    role_holders_read, strongbox_content, strongbox_content_read, unindex_viewer, vault_storage,
+    vault_storage_read, viewer_count_read, write_entropy_hash, write_entry,
+    write_owned_strongboxes, write_proposal, write_revision, write_roles, write_viewer_grant,
+    write_viewing_key, Activity, Beneficiary, ContractVersion, Expiration, Factory, Guardians,
+    Inheritance, Lockdown, Mode, Multisig, Proposal, Recovery, Revision, Role, State, Vault,
+    Viewer, ViewerGrant, ViewerScope, DEFAULT_HISTORY_DEPTH, DEFAULT_PAGE_SIZE, MAX_ENTROPY_LEN,
+    MAX_HISTORY_DEPTH, MAX_PAGE_SIZE, MAX_PROPOSAL_LIFETIME, MAX_RECOVERY_DELAY, MAX_SEED_LEN,
+    MIN_ENTROPY_LEN, MIN_INACTIVITY_PERIOD, MIN_RECOVERY_DELAY, MIN_SEED_LEN,
+    PREFIX_REVOKED_PERMITS,
 };
-use crate::viewing_key::{ViewingKey, VIEWING_KEY_SIZE};
+use crate::viewing_key::{KeyHashAlgorithm, ViewingKey, VIEWING_KEY_SIZE};
+
+pub const CONTRACT_NAME: &str = concat!("crates.io:", env!("CARGO_PKG_NAME"));
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const INSTANTIATE_STRONGBOX_REPLY_ID: u64 = 1;
 
 #[entry_point]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
-    let initial_seed = msg.serenity_seed;
+) -> Result<Response, ContractError> {
+    let initial_seed = secret_bytes(msg.serenity_seed, msg.serenity_seed_bytes);
     // Validate length
-    if initial_seed.len() != INITIAL_SEED_LEN {
-        return Err(StdError::generic_err("You need to provide valid seed"));
+    if initial_seed.len() < MIN_SEED_LEN || initial_seed.len() > MAX_SEED_LEN {
+        return Err(ContractError::InvalidSeedLength);
     }
 
-    let sender_address = deps.api.addr_canonicalize(info.sender.as_str())?;
+    // A factory instantiates strongboxes on behalf of their owner
+    let owner = msg.owner.as_ref().unwrap_or(&info.sender);
+    let owner_address = deps.api.addr_canonicalize(owner.as_str())?;
 
-    let state = State {
-        strongbox: String::from(""),
-        owner: sender_address,
-        serenity_seed: sha_256(&general_purpose::STANDARD.encode(&initial_seed).as_bytes())
-            .to_vec(),
-        entropy_hashes: vec![],
-    };
+    init_vault(
+        deps.storage,
+        owner_address,
+        sha_256(&general_purpose::STANDARD.encode(&initial_seed).as_bytes()).to_vec(),
+        &env.block,
+    )?;
 
-    config(deps.storage).save(&state)?;
+    let instance_mode = match msg.factory {
+        Some(code) => {
+            factory(deps.storage).save(&Factory {
+                code_id: code.code_id,
+                code_hash: code.code_hash,
+                created: 0,
+            })?;
+            Mode::Factory
+        }
+        None if msg.multi_tenant.unwrap_or(false) => Mode::MultiTenant,
+        None => Mode::Single,
+    };
+    mode(deps.storage).save(&instance_mode)?;
+    contract_version(deps.storage).save(&ContractVersion {
+        contract: CONTRACT_NAME.to_string(),
+        version: CONTRACT_VERSION.to_string(),
+    })?;
 
     deps.api
         .debug(format!("Contract was initialized by {}", info.sender).as_str());
     Ok(Response::default())
 }
 
+/// Bytes of a seed or entropy sent either as a string or as base64, which takes precedence
+fn secret_bytes(text: String, bytes: Option<Binary>) -> Vec<u8> {
+    match bytes {
+        Some(bytes) => bytes.0,
+        None => text.into_bytes(),
+    }
+}
+
+fn validate_entropy(entropy: &[u8]) -> Result<(), ContractError> {
+    if entropy.len() < MIN_ENTROPY_LEN || entropy.len() > MAX_ENTROPY_LEN {
+        return Err(ContractError::InvalidEntropyLength);
+    }
+    Ok(())
+}
+
+/// Writes an empty strongbox owned by `owner` into `storage`.
+fn init_vault(
+    storage: &mut dyn Storage,
+    owner: CanonicalAddr,
+    serenity_seed: Vec<u8>,
+    block: &BlockInfo,
+) -> StdResult<()> {
+    let state = State {
+        owner,
+        serenity_seed,
+        pending_owner: None,
+        revision: 0,
+        history_start: 0,
+        history_depth: DEFAULT_HISTORY_DEPTH,
+        key_epoch: 0,
+        lockdown: None,
+    };
+
+    write_revision(
+        storage,
+        &Revision {
+            revision: state.revision,
+            strongbox: String::from(""),
+            block_height: block.height,
+            block_time: block.time.seconds(),
+        },
+    )?;
+    config(storage).save(&state)?;
+    strongbox_content(storage).save(&String::from(""))
+}
+
 #[entry_point]
-pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    let mode = mode_read(deps.storage).may_load()?.unwrap_or_default();
+
+    // Select the vault, which defaults to the sender's own vault in multi-tenant mode
+    let (vault, msg) = match msg {
+        ExecuteMsg::CreateStrongbox {} if mode == Mode::Factory => {
+            return try_instantiate_strongbox(deps, env, info);
+        }
+        ExecuteMsg::WithVault { .. } | ExecuteMsg::CreateStrongbox {}
+            if mode != Mode::MultiTenant =>
+        {
+            return Err(ContractError::NotMultiTenant);
+        }
+        ExecuteMsg::WithVault { vault, msg } => (
+            Vault::Tenant(deps.api.addr_canonicalize(vault.as_str())?),
+            *msg,
+        ),
+        msg if mode == Mode::MultiTenant => (
+            Vault::Tenant(deps.api.addr_canonicalize(info.sender.as_str())?),
+            msg,
+        ),
+        msg => (Vault::Instance, msg),
+    };
+
     match msg {
-        ExecuteMsg::UpdateStrongbox { strongbox } => try_update_strongbox(deps, info, strongbox),
-        ExecuteMsg::CreateViewingKey {
-            entropy, viewer, ..
-        } => try_create_viewing_key(deps, env, info, entropy, viewer),
+        ExecuteMsg::CreateStrongbox {} => try_create_strongbox(deps, env, info),
+        // Permits are signed by accounts, so revoking one applies to every vault
+        ExecuteMsg::RevokePermit { name } => try_revoke_permit(deps, info, name),
+        msg => {
+            let mut vault_store = vault_storage(deps.storage, &vault);
+            if config_read(&vault_store).may_load()?.is_none() {
+                return Err(ContractError::VaultNotFound);
+            }
 
-        ExecuteMsg::TransferOwnership { new_owner } => {
-            try_transfer_ownership(deps, info, new_owner)
+            let vault_deps = DepsMut {
+                storage: &mut vault_store,
+                api: deps.api,
+                querier: deps.querier,
+            };
+            execute_vault(vault_deps, env, info, msg)
         }
+    }
+}
+
+fn execute_vault(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    // Any message signed by the owner resets the inheritance timer
+    let config_state: State = config_read(deps.storage).load()?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? == config_state.owner {
+        owner_activity(deps.storage).save(&Activity {
+            height: env.block.height,
+            time: env.block.time.seconds(),
+        })?;
+    }
+
+    match msg {
+        ExecuteMsg::UpdateStrongbox {
+            strongbox,
+            expected_revision,
+        } => try_update_strongbox(deps, env, info, strongbox, expected_revision),
+        ExecuteMsg::CreateViewingKey {
+            entropy,
+            entropy_bytes,
+            viewer,
+            scope,
+            expires_at_time,
+            expires_at_height,
+            label,
+            ..
+        } => try_create_viewing_key(
+            deps,
+            env,
+            info,
+            secret_bytes(entropy, entropy_bytes),
+            viewer,
+            ViewerGrant {
+                scope: scope.unwrap_or_default(),
+                expiration: Expiration {
+                    at_time: expires_at_time,
+                    at_height: expires_at_height,
+                },
+                label,
+                epoch: config_state.key_epoch,
+            },
+        ),
+
+        ExecuteMsg::ProposeOwnership { new_owner } => try_propose_ownership(deps, info, new_owner),
+        ExecuteMsg::AcceptOwnership {} => try_accept_ownership(deps, env, info),
+        ExecuteMsg::CancelOwnershipProposal {} => try_cancel_ownership_proposal(deps, info),
         ExecuteMsg::RevokeViewingKey { viewer } => try_revoke_viewing_key(deps, info, viewer),
+        ExecuteMsg::SetEntry { label, value } => try_set_entry(deps, info, label, value),
+        ExecuteMsg::RemoveEntry { label } => try_remove_entry(deps, info, label),
+        ExecuteMsg::RestoreRevision { revision } => try_restore_revision(deps, env, info, revision),
+        ExecuteMsg::SetHistoryDepth { depth } => try_set_history_depth(deps, info, depth),
+        ExecuteMsg::GrantViewer {
+            viewer,
+            scope,
+            expires_at_time,
+            expires_at_height,
+            label,
+        } => try_grant_viewer(
+            deps,
+            env,
+            info,
+            viewer,
+            ViewerGrant {
+                scope: scope.unwrap_or_default(),
+                expiration: Expiration {
+                    at_time: expires_at_time,
+                    at_height: expires_at_height,
+                },
+                label,
+                epoch: config_state.key_epoch,
+            },
+        ),
+        ExecuteMsg::RevokeViewerGrant { viewer } => try_revoke_viewer_grant(deps, info, viewer),
+        ExecuteMsg::SetViewingKey { key, .. } => try_set_viewing_key(deps, env, info, key),
+        ExecuteMsg::RotateSeed {
+            entropy,
+            entropy_bytes,
+        } => try_rotate_seed(deps, env, info, secret_bytes(entropy, entropy_bytes)),
+        ExecuteMsg::RevokeAllViewingKeys {} => try_revoke_all_viewing_keys(deps, info),
+        ExecuteMsg::Lockdown { until } => try_lockdown(deps, env, info, until),
+        ExecuteMsg::LiftLockdown {} => try_lift_lockdown(deps, info),
+        ExecuteMsg::SetGuardians {
+            guardians,
+            threshold,
+            delay,
+        } => try_set_guardians(deps, info, guardians, threshold, delay),
+        ExecuteMsg::InitiateRecovery { new_owner } => {
+            try_initiate_recovery(deps, env, info, new_owner)
+        }
+        ExecuteMsg::ApproveRecovery {} => try_approve_recovery(deps, info),
+        ExecuteMsg::CancelRecovery {} => try_cancel_recovery(deps, info),
+        ExecuteMsg::ExecuteRecovery {} => try_execute_recovery(deps, env),
+        ExecuteMsg::SetBeneficiary {
+            address,
+            inactivity_period,
+            inheritance,
+        } => try_set_beneficiary(
+            deps,
+            info,
+            address,
+            inactivity_period,
+            inheritance.unwrap_or_default(),
+        ),
+        ExecuteMsg::Heartbeat {} => try_heartbeat(deps, info),
+        ExecuteMsg::ClaimInheritance {} => try_claim_inheritance(deps, env, info),
+        ExecuteMsg::GrantRole { address, role } => try_grant_role(deps, info, address, role),
+        ExecuteMsg::RevokeRole { address, role } => try_revoke_role(deps, info, address, role),
+        ExecuteMsg::SetMultisig {
+            admins,
+            threshold,
+            proposal_lifetime,
+        } => try_set_multisig(deps, env, info, admins, threshold, proposal_lifetime),
+        ExecuteMsg::Propose { action } => try_propose(deps, env, info, *action),
+        ExecuteMsg::Approve { proposal_id } => try_approve(deps, env, info, proposal_id),
+        ExecuteMsg::CreateStrongbox {}
+        | ExecuteMsg::WithVault { .. }
+        | ExecuteMsg::RevokePermit { .. } => Err(ContractError::InvalidVaultMessage),
+    }
+}
+
+pub fn try_create_strongbox(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+
+    // Each vault gets its own seed derived from the instance seed
+    let instance_state = config_read(deps.storage).load()?;
+    let serenity_seed =
+        sha_256(&[instance_state.serenity_seed.as_slice(), sender.as_slice()].concat()).to_vec();
+
+    let mut vault_store = vault_storage(deps.storage, &Vault::Tenant(sender.clone()));
+    if config_read(&vault_store).may_load()?.is_some() {
+        return Err(ContractError::VaultExists);
+    }
+
+    init_vault(&mut vault_store, sender, serenity_seed, &env.block)?;
+
+    deps.api.debug("Strongbox created successfully");
+    Ok(Response::default())
+}
+
+/// Whether `account` is the owner or was granted `role`, or the admin role
+fn has_role(
+    storage: &dyn Storage,
+    state: &State,
+    account: &CanonicalAddr,
+    role: Role,
+) -> StdResult<bool> {
+    if *account == state.owner {
+        return Ok(true);
+    }
+
+    // Under a multisig every owner right goes through an approved proposal
+    if multisig_read(storage).may_load()?.is_some() {
+        return Ok(false);
+    }
+
+    let roles = read_roles(storage, account)?;
+    Ok(roles.contains(&Role::Admin) || roles.contains(&role))
+}
+
+pub fn try_grant_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: Addr,
+    role: Role,
+) -> Result<Response, ContractError> {
+    // Validate admin
+    let config_state: State = config_read(deps.storage).load()?;
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if !has_role(deps.storage, &config_state, &sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized);
+    }
+
+    let account = deps.api.addr_canonicalize(address.as_str())?;
+    let mut roles = read_roles(deps.storage, &account)?;
+    if !roles.contains(&role) {
+        roles.push(role);
+        write_roles(deps.storage, &account, &roles)?;
+    }
+
+    deps.api.debug("Role granted successfully");
+    Ok(Response::default())
+}
+
+pub fn try_revoke_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: Addr,
+    role: Role,
+) -> Result<Response, ContractError> {
+    // Validate admin
+    let config_state: State = config_read(deps.storage).load()?;
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if !has_role(deps.storage, &config_state, &sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized);
+    }
+
+    // Check role exists
+    let account = deps.api.addr_canonicalize(address.as_str())?;
+    let mut roles = read_roles(deps.storage, &account)?;
+    if !roles.contains(&role) {
+        return Err(ContractError::RoleNotFound);
     }
+
+    roles.retain(|granted| *granted != role);
+    write_roles(deps.storage, &account, &roles)?;
+
+    deps.api.debug("Role revoked successfully");
+    Ok(Response::default())
 }
 
 pub fn try_update_strongbox(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     strongbox: String,
-) -> StdResult<Response> {
+    expected_revision: Option<u64>,
+) -> Result<Response, ContractError> {
     let signer = deps.api.addr_canonicalize(info.sender.as_str())?;
 
-    config(deps.storage).update(|mut state| {
-        if signer != state.owner {
-            return Err(StdError::generic_err("You are not allowed"));
+    let mut state = config_read(deps.storage).load()?;
+    if !has_role(deps.storage, &state, &signer, Role::Writer)? {
+        return Err(ContractError::Unauthorized);
+    }
+
+    // Validate nobody updated the strongbox since the caller read it
+    if let Some(expected) = expected_revision {
+        if expected != state.revision {
+            return Err(ContractError::RevisionMismatch {
+                expected,
+                actual: state.revision,
+            });
         }
-        state.strongbox = strongbox;
-        Ok(state)
-    })?;
+    }
+
+    store_strongbox(deps.storage, &mut state, &env.block, strongbox)?;
+    config(deps.storage).save(&state)?;
 
     deps.api.debug("Strongbox updated successfully");
     Ok(Response::default())
 }
 
+pub fn try_restore_revision(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    revision: u64,
+) -> Result<Response, ContractError> {
+    let signer = deps.api.addr_canonicalize(info.sender.as_str())?;
+
+    let mut state = config_read(deps.storage).load()?;
+    if !has_role(deps.storage, &state, &signer, Role::Writer)? {
+        return Err(ContractError::Unauthorized);
+    }
+
+    let restored = read_revision(deps.storage, revision)?.ok_or(ContractError::RevisionNotFound)?;
+
+    store_strongbox(deps.storage, &mut state, &env.block, restored.strongbox)?;
+    config(deps.storage).save(&state)?;
+
+    deps.api.debug("Strongbox restored successfully");
+    Ok(Response::default())
+}
+
+pub fn try_set_history_depth(
+    deps: DepsMut,
+    info: MessageInfo,
+    depth: u32,
+) -> Result<Response, ContractError> {
+    let signer = deps.api.addr_canonicalize(info.sender.as_str())?;
+
+    let mut state = config_read(deps.storage).load()?;
+    if signer != state.owner {
+        return Err(ContractError::Unauthorized);
+    }
+
+    // Validate depth
+    if depth > MAX_HISTORY_DEPTH {
+        return Err(ContractError::InvalidHistoryDepth);
+    }
+
+    state.history_depth = depth;
+    prune_history(deps.storage, &mut state);
+    config(deps.storage).save(&state)?;
+
+    deps.api.debug("History depth updated successfully");
+    Ok(Response::default())
+}
+
+/// Makes `strongbox` the current revision and drops revisions beyond the history depth.
+fn store_strongbox(
+    storage: &mut dyn Storage,
+    state: &mut State,
+    block: &BlockInfo,
+    strongbox: String,
+) -> StdResult<()> {
+    state.revision += 1;
+    write_revision(
+        storage,
+        &Revision {
+            revision: state.revision,
+            strongbox: strongbox.clone(),
+            block_height: block.height,
+            block_time: block.time.seconds(),
+        },
+    )?;
+    strongbox_content(storage).save(&strongbox)?;
+
+    prune_history(storage, state);
+    Ok(())
+}
+
+fn prune_history(storage: &mut dyn Storage, state: &mut State) {
+    while state.revision - state.history_start > u64::from(state.history_depth) {
+        remove_revision(storage, state.history_start);
+        state.history_start += 1;
+    }
+}
+
+/// `grant` is the access given to `viewer`. A key the sender creates for itself gets the
+/// access of its own grant instead.
 pub fn try_create_viewing_key(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    entropy: String,
-    viewer: Addr,
-) -> StdResult<Response> {
+    entropy: Vec<u8>,
+    viewer: Option<Addr>,
+    grant: ViewerGrant,
+) -> Result<Response, ContractError> {
     // Validate length
-    if entropy.len() != ENTROPY_LEN {
-        return Err(StdError::generic_err("You need to provide valid entropy"));
-    }
+    validate_entropy(&entropy)?;
 
-    // Validate owner
     let config_state: State = config_read(deps.storage).load()?;
     let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
-    if sender != config_state.owner {
-        return Err(StdError::generic_err("You are not allowed"));
-    }
 
-    // Validate duplicate entropy
-    let entropy_hash = to_binary(&sha_256(&entropy.as_bytes()))?;
-    let duplicated = config_state
-        .entropy_hashes
-        .iter()
-        .find(|&x| x.eq(&entropy_hash));
+    // Key managers mint keys for any viewer, other accounts create their own key
+    let (viewer_addr, grant) = match viewer {
+        Some(viewer) => {
+            if !has_role(deps.storage, &config_state, &sender, Role::KeyManager)? {
+                return Err(ContractError::Unauthorized);
+            }
 
-    // Store entropy hash
-    config(deps.storage).update(|mut state| {
-        // Check entropy is duplicated
-        if duplicated.is_some() {
-            return Err(StdError::generic_err("You need to use another entropy"));
+            // Validate expiration
+            if grant.expiration.is_expired(&env.block) {
+                return Err(ContractError::InvalidExpiration);
+            }
+
+            (deps.api.addr_canonicalize(viewer.as_str())?, grant)
+        }
+        None => {
+            let grant = own_key_access(deps.storage, &config_state, &sender, &env.block)?;
+            (sender.clone(), grant)
         }
+    };
 
-        state.entropy_hashes.push(entropy_hash);
-        Ok(state)
-    })?;
+    // Validate duplicate entropy
+    let entropy_hash = to_binary(&sha_256(&entropy))?;
+    if has_entropy_hash(deps.storage, entropy_hash.as_slice()) {
+        return Err(ContractError::DuplicateEntropy);
+    }
+
+    // Store entropy hash
+    write_entropy_hash(deps.storage, entropy_hash.as_slice());
 
     // Generate viewing key
     let prng_seed = config_state.serenity_seed;
 
-    let key = ViewingKey::new(&env, &sender, &prng_seed, (&entropy).as_ref());
+    let key = ViewingKey::new(&env, &sender, &prng_seed, &entropy);
+    let contract = deps.api.addr_canonicalize(env.contract.address.as_str())?;
     write_viewing_key(
         deps.storage,
-        &deps.api.addr_canonicalize(viewer.as_str())?,
+        &contract,
+        &viewer_addr,
         &key,
-    );
+        grant.scope,
+        grant.expiration,
+        config_state.key_epoch,
+    )?;
+    index_viewer(
+        deps.storage,
+        &Viewer {
+            address: viewer_addr,
+            label: grant.label,
+            created_height: env.block.height,
+            created_time: env.block.time.seconds(),
+        },
+    )?;
 
-    let response = Response::default().set_data(to_binary(&key)?);
+    let response = Response::default().set_data(to_binary(&ExecuteAnswer::CreateViewingKey(
+        ViewingKeyResponse { key },
+    ))?);
     Ok(response)
 }
 
-pub fn try_transfer_ownership(
+/// Access of a key the sender creates or sets for itself. Key managers read everything,
+/// other accounts need a grant.
+fn own_key_access(
+    storage: &dyn Storage,
+    state: &State,
+    sender: &CanonicalAddr,
+    block: &BlockInfo,
+) -> Result<ViewerGrant, ContractError> {
+    if has_role(storage, state, sender, Role::KeyManager)? {
+        return Ok(ViewerGrant {
+            scope: ViewerScope::All,
+            expiration: Expiration::default(),
+            label: None,
+            epoch: state.key_epoch,
+        });
+    }
+
+    // Grants made before the last RevokeAllViewingKeys are no longer valid
+    let grant = match read_viewer_grant(storage, sender)? {
+        Some(grant) if grant.epoch == state.key_epoch => grant,
+        _ => return Err(ContractError::Unauthorized),
+    };
+    if grant.expiration.is_expired(block) {
+        return Err(ContractError::GrantExpired);
+    }
+
+    Ok(grant)
+}
+
+pub fn try_set_viewing_key(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    new_owner: Addr,
-) -> StdResult<Response> {
-    let signer = deps.api.addr_canonicalize(info.sender.as_str())?;
+    key: String,
+) -> Result<Response, ContractError> {
+    let config_state: State = config_read(deps.storage).load()?;
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
 
-    let new_owner_addr = deps.api.addr_canonicalize(new_owner.as_str())?;
+    let grant = own_key_access(deps.storage, &config_state, &sender, &env.block)?;
 
-    config(deps.storage).update(|mut state| {
-        if signer != state.owner {
-            return Err(StdError::generic_err("You are not allowed"));
-        }
+    let contract = deps.api.addr_canonicalize(env.contract.address.as_str())?;
+    write_viewing_key(
+        deps.storage,
+        &contract,
+        &sender,
+        &ViewingKey(key),
+        grant.scope,
+        grant.expiration,
+        config_state.key_epoch,
+    )?;
+    index_viewer(
+        deps.storage,
+        &Viewer {
+            address: sender,
+            label: grant.label,
+            created_height: env.block.height,
+            created_time: env.block.time.seconds(),
+        },
+    )?;
 
-        state.owner = new_owner_addr;
-        Ok(state)
-    })?;
+    deps.api.debug("Viewing key set successfully");
+    Ok(
+        Response::default().set_data(to_binary(&ExecuteAnswer::SetViewingKey {
+            status: ResponseStatus::Success,
+        })?),
+    )
+}
 
-    deps.api.debug("Owner updated successfully");
+pub fn try_grant_viewer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    viewer: Addr,
+    grant: ViewerGrant,
+) -> Result<Response, ContractError> {
+    // Validate owner
+    let config_state: State = config_read(deps.storage).load()?;
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if sender != config_state.owner {
+        return Err(ContractError::Unauthorized);
+    }
+
+    // Validate expiration
+    if grant.expiration.is_expired(&env.block) {
+        return Err(ContractError::InvalidExpiration);
+    }
+
+    let viewer_addr = deps.api.addr_canonicalize(viewer.as_str())?;
+    write_viewer_grant(deps.storage, &viewer_addr, &grant)?;
+
+    deps.api.debug("Viewer granted successfully");
     Ok(Response::default())
 }
 
-pub fn try_revoke_viewing_key(
+pub fn try_revoke_viewer_grant(
     deps: DepsMut,
     info: MessageInfo,
     viewer: Addr,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     // Validate owner
     let config_state: State = config_read(deps.storage).load()?;
     let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
     if sender != config_state.owner {
-        return Err(StdError::generic_err("You are not allowed"));
+        return Err(ContractError::Unauthorized);
     }
 
-    // Check viewing key exists
+    // Check grant exists
     let viewer_addr = deps.api.addr_canonicalize(viewer.as_str())?;
-    let viewer_key = read_viewing_key(deps.storage, &viewer_addr);
-    if viewer_key.is_none() {
-        return Err(StdError::generic_err("Viewing key not exists"));
+    if read_viewer_grant(deps.storage, &viewer_addr)?.is_none() {
+        return Err(ContractError::GrantNotFound);
     }
 
-    revoke_viewing_key(deps.storage, &viewer_addr);
+    remove_viewer_grant(deps.storage, &viewer_addr);
 
-    deps.api.debug("Viewing key revoked successfully");
+    deps.api.debug("Viewer grant revoked successfully");
     Ok(Response::default())
 }
 
-#[entry_point]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    let (addresses, key) = msg.get_validation_params();
+pub fn try_propose_ownership(
+    deps: DepsMut,
+    info: MessageInfo,
+    new_owner: Addr,
+) -> Result<Response, ContractError> {
+    let signer = deps.api.addr_canonicalize(info.sender.as_str())?;
 
-    for address in addresses {
-        let canonical_addr = deps.api.addr_canonicalize(address.as_str())?;
+    let new_owner_addr = deps.api.addr_canonicalize(new_owner.as_str())?;
 
-        let expected_key = read_viewing_key(deps.storage, &canonical_addr);
+    let mut state = config_read(deps.storage).load()?;
+    if signer != state.owner {
+        return Err(ContractError::Unauthorized);
+    }
 
-        if expected_key.is_none() {
-            // Checking the key will take significant time. We don't want to exit immediately if it isn't set
-            // in a way which will allow to time the command and determine if a viewing key doesn't exist
-            key.check_viewing_key(&[0u8; VIEWING_KEY_SIZE]);
-        } else if key.check_viewing_key(expected_key.unwrap().as_slice()) {
-            return match msg {
-                QueryMsg::GetStrongbox { .. } => to_binary(&query_strongbox(deps)?),
-            };
+    state.pending_owner = Some(new_owner_addr);
+    config(deps.storage).save(&state)?;
+
+    deps.api.debug("Ownership proposed successfully");
+    Ok(Response::default())
+}
+
+pub fn try_accept_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let signer = deps.api.addr_canonicalize(info.sender.as_str())?;
+
+    let state = config_read(deps.storage).load()?;
+    match state.pending_owner {
+        None => return Err(ContractError::OwnershipProposalNotFound),
+        Some(pending_owner) if pending_owner != signer => {
+            return Err(ContractError::Unauthorized);
         }
+        Some(_) => {}
     }
 
-    Err(StdError::generic_err("Your viewing key does not matched"))
+    change_owner(deps.storage, &env.block, signer)?;
+
+    deps.api.debug("Owner updated successfully");
+    Ok(Response::default())
+}
+
+pub fn try_cancel_ownership_proposal(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let signer = deps.api.addr_canonicalize(info.sender.as_str())?;
+
+    config(deps.storage).update(|mut state| {
+        if signer != state.owner {
+            return Err(ContractError::Unauthorized);
+        }
+        if state.pending_owner.is_none() {
+            return Err(ContractError::OwnershipProposalNotFound);
+        }
+
+        state.pending_owner = None;
+        Ok(state)
+    })?;
+
+    deps.api.debug("Ownership proposal cancelled successfully");
+    Ok(Response::default())
+}
+
+/// Hands the strongbox to `new_owner`. The roles, the guardians, a recovery in progress, the
+/// beneficiary and the multisig were set up for the previous owner, so they are dropped, and the
+/// new owner's inactivity starts now.
+fn change_owner(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    new_owner: CanonicalAddr,
+) -> StdResult<()> {
+    config(storage).update(|mut state| -> StdResult<_> {
+        state.owner = new_owner;
+        state.pending_owner = None;
+        Ok(state)
+    })?;
+    clear_roles(storage)?;
+    guardians(storage).remove();
+    recovery(storage).remove();
+    beneficiary(storage).remove();
+    multisig(storage).remove();
+    owner_activity(storage).save(&Activity {
+        height: block.height,
+        time: block.time.seconds(),
+    })
 }
 
-fn query_strongbox(deps: Deps) -> StdResult<StrongboxResponse> {
-    let mut _strongbox = String::from("");
-    let state = config_read(deps.storage).load()?;
-    _strongbox = state.strongbox;
+pub fn try_set_guardians(
+    deps: DepsMut,
+    info: MessageInfo,
+    new_guardians: Vec<Addr>,
+    threshold: u32,
+    delay: u64,
+) -> Result<Response, ContractError> {
+    // Validate owner
+    let config_state: State = config_read(deps.storage).load()?;
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if sender != config_state.owner {
+        return Err(ContractError::Unauthorized);
+    }
+
+    // A recovery approved by the previous guardians doesn't carry over
+    recovery(deps.storage).remove();
+
+    if new_guardians.is_empty() {
+        guardians(deps.storage).remove();
+
+        deps.api.debug("Guardians removed successfully");
+        return Ok(Response::default());
+    }
+
+    // Validate guardians
+    let mut addresses: Vec<CanonicalAddr> = vec![];
+    for guardian in new_guardians {
+        let address = deps.api.addr_canonicalize(guardian.as_str())?;
+        if address == config_state.owner || addresses.contains(&address) {
+            return Err(ContractError::InvalidGuardians);
+        }
+        addresses.push(address);
+    }
+    if threshold == 0 || threshold as usize > addresses.len() {
+        return Err(ContractError::InvalidGuardians);
+    }
+
+    // Validate delay
+    if delay < MIN_RECOVERY_DELAY || delay > MAX_RECOVERY_DELAY {
+        return Err(ContractError::InvalidRecoveryDelay);
+    }
+
+    guardians(deps.storage).save(&Guardians {
+        addresses,
+        threshold,
+        delay,
+    })?;
+
+    deps.api.debug("Guardians updated successfully");
+    Ok(Response::default())
+}
+
+fn ensure_guardian(storage: &dyn Storage, account: &CanonicalAddr) -> Result<(), ContractError> {
+    match guardians_read(storage).may_load()? {
+        Some(guardian_set) if guardian_set.addresses.contains(account) => Ok(()),
+        _ => Err(ContractError::Unauthorized),
+    }
+}
+
+pub fn try_initiate_recovery(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_owner: Addr,
+) -> Result<Response, ContractError> {
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    ensure_guardian(deps.storage, &sender)?;
+
+    if recovery_read(deps.storage).may_load()?.is_some() {
+        return Err(ContractError::RecoveryInProgress);
+    }
+
+    recovery(deps.storage).save(&Recovery {
+        new_owner: deps.api.addr_canonicalize(new_owner.as_str())?,
+        approvals: vec![sender],
+        initiated_at: env.block.time.seconds(),
+    })?;
+
+    deps.api.debug("Recovery initiated successfully");
+    Ok(Response::default())
+}
+
+pub fn try_approve_recovery(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    ensure_guardian(deps.storage, &sender)?;
+
+    let mut current = recovery_read(deps.storage)
+        .may_load()?
+        .ok_or(ContractError::RecoveryNotFound)?;
+    if !current.approvals.contains(&sender) {
+        current.approvals.push(sender);
+        recovery(deps.storage).save(&current)?;
+    }
+
+    deps.api.debug("Recovery approved successfully");
+    Ok(Response::default())
+}
+
+pub fn try_cancel_recovery(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    // Validate owner
+    let config_state: State = config_read(deps.storage).load()?;
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if sender != config_state.owner {
+        return Err(ContractError::Unauthorized);
+    }
+
+    // Check recovery exists
+    if recovery_read(deps.storage).may_load()?.is_none() {
+        return Err(ContractError::RecoveryNotFound);
+    }
+
+    recovery(deps.storage).remove();
+
+    deps.api.debug("Recovery cancelled successfully");
+    Ok(Response::default())
+}
+
+pub fn try_execute_recovery(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let current = recovery_read(deps.storage)
+        .may_load()?
+        .ok_or(ContractError::RecoveryNotFound)?;
+    let guardian_set = guardians_read(deps.storage).load()?;
+
+    // Validate approvals and delay
+    if (current.approvals.len() as u32) < guardian_set.threshold
+        || env.block.time.seconds() < current.initiated_at + guardian_set.delay
+    {
+        return Err(ContractError::RecoveryNotReady);
+    }
+
+    change_owner(deps.storage, &env.block, current.new_owner)?;
+
+    deps.api.debug("Ownership recovered successfully");
+    Ok(Response::default())
+}
+
+pub fn try_set_beneficiary(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: Addr,
+    inactivity_period: u64,
+    inheritance: Inheritance,
+) -> Result<Response, ContractError> {
+    // Validate owner
+    let config_state: State = config_read(deps.storage).load()?;
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if sender != config_state.owner {
+        return Err(ContractError::Unauthorized);
+    }
+
+    // Validate inactivity period
+    if inactivity_period < MIN_INACTIVITY_PERIOD {
+        return Err(ContractError::InvalidInactivityPeriod);
+    }
+
+    beneficiary(deps.storage).save(&Beneficiary {
+        address: deps.api.addr_canonicalize(address.as_str())?,
+        inactivity_period,
+        inheritance,
+    })?;
+
+    deps.api.debug("Beneficiary set successfully");
+    Ok(Response::default())
+}
+
+pub fn try_heartbeat(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    // Validate owner
+    let config_state: State = config_read(deps.storage).load()?;
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if sender != config_state.owner {
+        return Err(ContractError::Unauthorized);
+    }
+
+    Ok(Response::default())
+}
+
+pub fn try_claim_inheritance(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    // Validate beneficiary
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let heir = match beneficiary_read(deps.storage).may_load()? {
+        Some(heir) if heir.address == sender => heir,
+        _ => return Err(ContractError::Unauthorized),
+    };
+
+    // Validate inactivity
+    let last_activity = owner_activity_read(deps.storage).load()?;
+    if env.block.time.seconds().saturating_sub(last_activity.time) < heir.inactivity_period {
+        return Err(ContractError::InheritanceNotReady);
+    }
+
+    beneficiary(deps.storage).remove();
+
+    let response = match heir.inheritance {
+        Inheritance::Ownership => {
+            change_owner(deps.storage, &env.block, sender)?;
+
+            Response::default()
+        }
+        Inheritance::Viewer => {
+            let config_state: State = config_read(deps.storage).load()?;
+            let key = ViewingKey::new(&env, &sender, &config_state.serenity_seed, b"inheritance");
+            let contract = deps.api.addr_canonicalize(env.contract.address.as_str())?;
+            write_viewing_key(
+                deps.storage,
+                &contract,
+                &sender,
+                &key,
+                ViewerScope::All,
+                Expiration::default(),
+                config_state.key_epoch,
+            )?;
+            index_viewer(
+                deps.storage,
+                &Viewer {
+                    address: sender,
+                    label: None,
+                    created_height: env.block.height,
+                    created_time: env.block.time.seconds(),
+                },
+            )?;
+
+            Response::default().set_data(to_binary(&ExecuteAnswer::CreateViewingKey(
+                ViewingKeyResponse { key },
+            ))?)
+        }
+    };
+
+    deps.api.debug("Inheritance claimed successfully");
+    Ok(response)
+}
+
+pub fn try_set_multisig(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    admins: Vec<Addr>,
+    threshold: u32,
+    proposal_lifetime: u64,
+) -> Result<Response, ContractError> {
+    // Validate owner
+    let config_state: State = config_read(deps.storage).load()?;
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if sender != config_state.owner {
+        return Err(ContractError::Unauthorized);
+    }
+
+    // Validate admins
+    let mut addresses: Vec<CanonicalAddr> = vec![];
+    for admin in admins {
+        let address = deps.api.addr_canonicalize(admin.as_str())?;
+        if addresses.contains(&address) {
+            return Err(ContractError::InvalidMultisig);
+        }
+        addresses.push(address);
+    }
+    if threshold == 0 || threshold as usize > addresses.len() {
+        return Err(ContractError::InvalidMultisig);
+    }
+
+    // Validate proposal lifetime
+    if proposal_lifetime == 0 || proposal_lifetime > MAX_PROPOSAL_LIFETIME {
+        return Err(ContractError::InvalidMultisig);
+    }
+
+    // Owner checks now only pass for approved proposals, executed by the contract
+    let contract = deps.api.addr_canonicalize(env.contract.address.as_str())?;
+    change_owner(deps.storage, &env.block, contract)?;
+
+    // Proposals approved by the previous admins don't carry over
+    let first_proposal = proposal_count_read(deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    multisig(deps.storage).save(&Multisig {
+        admins: addresses,
+        threshold,
+        proposal_lifetime,
+        first_proposal,
+    })?;
+
+    deps.api.debug("Multisig set successfully");
+    Ok(Response::default())
+}
+
+fn ensure_admin(deps: Deps, env: &Env, account: &CanonicalAddr) -> Result<Multisig, ContractError> {
+    // The multisig stops applying once ownership was handed to someone else
+    let config_state: State = config_read(deps.storage).load()?;
+    if config_state.owner != deps.api.addr_canonicalize(env.contract.address.as_str())? {
+        return Err(ContractError::Unauthorized);
+    }
+
+    match multisig_read(deps.storage).may_load()? {
+        Some(multisig_config) if multisig_config.admins.contains(account) => Ok(multisig_config),
+        _ => Err(ContractError::Unauthorized),
+    }
+}
+
+pub fn try_propose(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    action: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let multisig_config = ensure_admin(deps.as_ref(), &env, &sender)?;
+
+    // Validate action
+    if matches!(
+        action,
+        ExecuteMsg::Propose { .. }
+            | ExecuteMsg::Approve { .. }
+            | ExecuteMsg::CreateStrongbox {}
+            | ExecuteMsg::WithVault { .. }
+            | ExecuteMsg::RevokePermit { .. }
+    ) {
+        return Err(ContractError::InvalidProposal);
+    }
+
+    let proposal_id = proposal_count_read(deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    proposal_count(deps.storage).save(&(proposal_id + 1))?;
+
+    let proposal = Proposal {
+        action: to_binary(&action)?,
+        approvals: vec![sender],
+        expires_at: env.block.time.seconds() + multisig_config.proposal_lifetime,
+    };
+
+    let response = execute_if_approved(deps, env, &multisig_config, proposal_id, proposal)?;
+    Ok(response.add_attribute("proposal_id", proposal_id.to_string()))
+}
+
+pub fn try_approve(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+) -> Result<Response, ContractError> {
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let multisig_config = ensure_admin(deps.as_ref(), &env, &sender)?;
+
+    if proposal_id < multisig_config.first_proposal {
+        return Err(ContractError::ProposalNotFound);
+    }
+    let mut proposal =
+        read_proposal(deps.storage, proposal_id)?.ok_or(ContractError::ProposalNotFound)?;
+    if env.block.time.seconds() >= proposal.expires_at {
+        return Err(ContractError::ProposalExpired);
+    }
+
+    if !proposal.approvals.contains(&sender) {
+        proposal.approvals.push(sender);
+    }
+
+    execute_if_approved(deps, env, &multisig_config, proposal_id, proposal)
+}
+
+fn execute_if_approved(
+    deps: DepsMut,
+    env: Env,
+    multisig_config: &Multisig,
+    proposal_id: u64,
+    proposal: Proposal,
+) -> Result<Response, ContractError> {
+    // Only approvals of current admins count
+    let approvals = proposal
+        .approvals
+        .iter()
+        .filter(|approval| multisig_config.admins.contains(approval))
+        .count();
+    if (approvals as u32) < multisig_config.threshold {
+        write_proposal(deps.storage, proposal_id, &proposal)?;
+
+        deps.api.debug("Proposal approved successfully");
+        return Ok(Response::default());
+    }
+
+    remove_proposal(deps.storage, proposal_id);
+
+    // Run the action as the contract, which owns the strongbox
+    let action: ExecuteMsg = from_binary(&proposal.action)?;
+    let info = MessageInfo {
+        sender: env.contract.address.clone(),
+        funds: vec![],
+    };
+    execute_vault(deps, env, info, action)
+}
+
+pub fn try_revoke_viewing_key(
+    deps: DepsMut,
+    info: MessageInfo,
+    viewer: Addr,
+) -> Result<Response, ContractError> {
+    // Validate key manager
+    let config_state: State = config_read(deps.storage).load()?;
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if !has_role(deps.storage, &config_state, &sender, Role::KeyManager)? {
+        return Err(ContractError::Unauthorized);
+    }
+
+    // Check viewing key exists
+    let viewer_addr = deps.api.addr_canonicalize(viewer.as_str())?;
+    let viewer_key = read_viewing_key(deps.storage, &viewer_addr);
+    if viewer_key.is_none() {
+        return Err(ContractError::ViewingKeyNotFound);
+    }
+
+    // The viewer can't set up a new key without being granted again
+    revoke_viewing_key(deps.storage, &viewer_addr);
+    remove_viewer_grant(deps.storage, &viewer_addr);
+    unindex_viewer(deps.storage, &viewer_addr)?;
+
+    deps.api.debug("Viewing key revoked successfully");
+    Ok(Response::default())
+}
+
+pub fn try_rotate_seed(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    entropy: Vec<u8>,
+) -> Result<Response, ContractError> {
+    // Validate length
+    validate_entropy(&entropy)?;
+
+    // Validate owner
+    let mut config_state: State = config_read(deps.storage).load()?;
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if sender != config_state.owner {
+        return Err(ContractError::Unauthorized);
+    }
+
+    let random = env.block.random.unwrap_or_default();
+    config_state.serenity_seed = sha_256(
+        &[
+            config_state.serenity_seed.as_slice(),
+            entropy.as_slice(),
+            random.as_slice(),
+        ]
+        .concat(),
+    )
+    .to_vec();
+    config(deps.storage).save(&config_state)?;
+
+    deps.api.debug("Seed rotated successfully");
+    Ok(Response::default())
+}
+
+pub fn try_revoke_all_viewing_keys(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let signer = deps.api.addr_canonicalize(info.sender.as_str())?;
+
+    // Keys written under an older epoch stop working
+    config(deps.storage).update(|mut state| {
+        if signer != state.owner {
+            return Err(ContractError::Unauthorized);
+        }
+
+        state.key_epoch += 1;
+        Ok(state)
+    })?;
+
+    deps.api.debug("All viewing keys revoked successfully");
+    Ok(Response::default())
+}
+
+pub fn try_lockdown(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    until: Option<u64>,
+) -> Result<Response, ContractError> {
+    let signer = deps.api.addr_canonicalize(info.sender.as_str())?;
+
+    // Validate until
+    if matches!(until, Some(until) if until <= env.block.time.seconds()) {
+        return Err(ContractError::InvalidExpiration);
+    }
+
+    config(deps.storage).update(|mut state| {
+        if signer != state.owner {
+            return Err(ContractError::Unauthorized);
+        }
+
+        state.lockdown = Some(Lockdown { until });
+        Ok(state)
+    })?;
+
+    deps.api.debug("Strongbox locked down successfully");
+    Ok(Response::default())
+}
+
+pub fn try_lift_lockdown(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let signer = deps.api.addr_canonicalize(info.sender.as_str())?;
+
+    config(deps.storage).update(|mut state| {
+        if signer != state.owner {
+            return Err(ContractError::Unauthorized);
+        }
+
+        state.lockdown = None;
+        Ok(state)
+    })?;
+
+    deps.api.debug("Lockdown lifted successfully");
+    Ok(Response::default())
+}
+
+pub fn try_set_entry(
+    deps: DepsMut,
+    info: MessageInfo,
+    label: String,
+    value: String,
+) -> Result<Response, ContractError> {
+    // Validate writer
+    let config_state: State = config_read(deps.storage).load()?;
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if !has_role(deps.storage, &config_state, &sender, Role::Writer)? {
+        return Err(ContractError::Unauthorized);
+    }
+
+    // Validate label
+    if label.is_empty() {
+        return Err(ContractError::InvalidLabel);
+    }
+
+    let mut labels = entry_labels_read(deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    if !labels.contains(&label) {
+        labels.push(label.clone());
+        entry_labels(deps.storage).save(&labels)?;
+    }
+
+    write_entry(deps.storage, &label, &value);
+
+    deps.api.debug("Entry updated successfully");
+    Ok(Response::default())
+}
+
+pub fn try_remove_entry(
+    deps: DepsMut,
+    info: MessageInfo,
+    label: String,
+) -> Result<Response, ContractError> {
+    // Validate writer
+    let config_state: State = config_read(deps.storage).load()?;
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if !has_role(deps.storage, &config_state, &sender, Role::Writer)? {
+        return Err(ContractError::Unauthorized);
+    }
+
+    // Check entry exists
+    if read_entry(deps.storage, &label).is_none() {
+        return Err(ContractError::EntryNotFound);
+    }
+
+    let mut labels = entry_labels_read(deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    labels.retain(|x| x != &label);
+    entry_labels(deps.storage).save(&labels)?;
+
+    remove_entry(deps.storage, &label);
+
+    deps.api.debug("Entry removed successfully");
+    Ok(Response::default())
+}
+
+pub fn try_instantiate_strongbox(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+
+    let mut factory_state = factory_read(deps.storage).load()?;
+    factory_state.created += 1;
+    factory(deps.storage).save(&factory_state)?;
+
+    // Derive a seed for the new strongbox from the factory seed
+    let instance_state = config_read(deps.storage).load()?;
+    let seed_hash = sha_256(
+        &[
+            instance_state.serenity_seed.as_slice(),
+            sender.as_slice(),
+            factory_state.created.to_be_bytes().as_slice(),
+        ]
+        .concat(),
+    );
+    let serenity_seed = general_purpose::STANDARD.encode(&seed_hash[..24]);
+
+    // The reply records the new strongbox for its owner
+    pending_instance_owner(deps.storage).save(&sender)?;
+
+    // The owner can migrate their strongbox to a newer code
+    let instantiate_msg = WasmMsg::Instantiate {
+        admin: Some(info.sender.to_string()),
+        code_id: factory_state.code_id,
+        code_hash: factory_state.code_hash,
+        msg: to_binary(&InstantiateMsg {
+            serenity_seed,
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: Some(info.sender),
+        })?,
+        funds: vec![],
+        label: format!(
+            "Serenity Strongbox_{}_{}",
+            env.contract.address, factory_state.created
+        ),
+    };
+
+    Ok(Response::new().add_submessage(SubMsg::reply_on_success(
+        instantiate_msg,
+        INSTANTIATE_STRONGBOX_REPLY_ID,
+    )))
+}
+
+#[entry_point]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        INSTANTIATE_STRONGBOX_REPLY_ID => handle_instantiate_strongbox_reply(deps, msg),
+        id => Err(ContractError::UnknownReply { id }),
+    }
+}
+
+fn handle_instantiate_strongbox_reply(
+    deps: DepsMut,
+    msg: Reply,
+) -> Result<Response, ContractError> {
+    let response = msg
+        .result
+        .into_result()
+        .map_err(|error| ContractError::StrongboxInstantiationFailed { error })?;
+    let contract_address = response
+        .events
+        .iter()
+        .filter(|event| event.ty == "instantiate")
+        .flat_map(|event| event.attributes.iter())
+        .find(|attribute| attribute.key == "contract_address")
+        .ok_or(ContractError::StrongboxAddressNotFound)?;
+    let strongbox = deps.api.addr_validate(&contract_address.value)?;
+
+    let owner = pending_instance_owner_read(deps.storage).load()?;
+    pending_instance_owner(deps.storage).remove();
+
+    let mut strongboxes = read_owned_strongboxes(deps.storage, &owner)?;
+    strongboxes.push(strongbox.clone());
+    write_owned_strongboxes(deps.storage, &owner, &strongboxes)?;
+
+    deps.api
+        .debug(format!("Strongbox {} was instantiated", strongbox).as_str());
+    Ok(Response::new().add_attribute("strongbox", strongbox))
+}
+
+pub fn try_revoke_permit(
+    deps: DepsMut,
+    info: MessageInfo,
+    name: String,
+) -> Result<Response, ContractError> {
+    RevokedPermits::revoke_permit(
+        deps.storage,
+        PREFIX_REVOKED_PERMITS,
+        info.sender.as_str(),
+        &name,
+    );
+
+    deps.api.debug("Permit revoked successfully");
+    Ok(Response::default())
+}
+
+#[entry_point]
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    match contract_version_read(deps.storage).may_load()? {
+        // v0.1.0 did not record its version
+        None => migrate_from_v0_1_0(deps.storage, &env.block)?,
+        Some(stored) if stored.contract != CONTRACT_NAME => {
+            return Err(ContractError::InvalidMigration {
+                contract: stored.contract,
+            });
+        }
+        Some(stored) => {
+            // Downgrades would leave the state in a layout the code doesn't know about
+            let stored_version = parse_version(&stored.version)
+                .filter(|version| Some(*version) <= parse_version(CONTRACT_VERSION))
+                .ok_or(ContractError::InvalidMigrationVersion {
+                    version: stored.version,
+                })?;
+            if stored_version < [0, 3, 0] {
+                migrate_from_v0_2_0(deps.storage)?;
+            }
+        }
+    }
+
+    contract_version(deps.storage).save(&ContractVersion {
+        contract: CONTRACT_NAME.to_string(),
+        version: CONTRACT_VERSION.to_string(),
+    })?;
+
+    deps.api
+        .debug(format!("Contract was migrated to {}", CONTRACT_VERSION).as_str());
+    Ok(Response::default())
+}
+
+/// Major, minor and patch numbers of a `major.minor.patch` version
+fn parse_version(version: &str) -> Option<[u64; 3]> {
+    let mut numbers = version.split('.');
+    let mut parsed = [0u64; 3];
+    for number in parsed.iter_mut() {
+        *number = numbers.next()?.parse().ok()?;
+    }
+
+    numbers.next().is_none().then_some(parsed)
+}
+
+fn migrate_from_v0_1_0(storage: &mut dyn Storage, block: &BlockInfo) -> StdResult<()> {
+    let legacy = legacy_config_read(storage).load()?;
+
+    let state = State {
+        owner: legacy.owner,
+        serenity_seed: legacy.serenity_seed,
+        pending_owner: None,
+        revision: 0,
+        history_start: 0,
+        history_depth: DEFAULT_HISTORY_DEPTH,
+        key_epoch: 0,
+        lockdown: None,
+    };
+
+    for entropy_hash in &legacy.entropy_hashes {
+        write_entropy_hash(storage, entropy_hash.as_slice());
+    }
+
+    // The existing strongbox becomes the first revision
+    write_revision(
+        storage,
+        &Revision {
+            revision: state.revision,
+            strongbox: legacy.strongbox.clone(),
+            block_height: block.height,
+            block_time: block.time.seconds(),
+        },
+    )?;
+    strongbox_content(storage).save(&legacy.strongbox)?;
+    config(storage).save(&state)
+}
+
+/// Moves the strongbox and entropy hashes out of `State`, where versions before 0.3.0 kept them.
+fn migrate_from_v0_2_0(storage: &mut dyn Storage) -> StdResult<()> {
+    let legacy = legacy_state_fields_read(storage).load()?;
+    if let Some(strongbox) = legacy.strongbox {
+        strongbox_content(storage).save(&strongbox)?;
+    }
+    for entropy_hash in &legacy.entropy_hashes {
+        write_entropy_hash(storage, entropy_hash.as_slice());
+    }
+
+    // Saving the state again drops the moved fields from it
+    let state = config_read(storage).load()?;
+    config(storage).save(&state)
+}
+
+#[entry_point]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    let mode = mode_read(deps.storage).may_load()?.unwrap_or_default();
+    let vault = match msg.get_vault() {
+        // The instance vault of a multi-tenant contract only holds the seed of the tenant vaults
+        None if mode == Mode::MultiTenant => return Err(ContractError::VaultNotFound),
+        None => Vault::Instance,
+        Some(_) if mode != Mode::MultiTenant => return Err(ContractError::NotMultiTenant),
+        Some(creator) => Vault::Tenant(deps.api.addr_canonicalize(creator.as_str())?),
+    };
+    let vault_store = vault_storage_read(deps.storage, &vault);
+    let vault_deps = Deps {
+        storage: &vault_store,
+        api: deps.api,
+        querier: deps.querier,
+    };
+
+    if let QueryMsg::WithPermit { permit, query, .. } = msg {
+        return permit_queries(deps, vault_deps, env, permit, query);
+    }
+
+    let (addresses, key) = msg
+        .get_validation_params()
+        .ok_or(ContractError::InvalidViewingKey)?;
+    let contract = deps.api.addr_canonicalize(env.contract.address.as_str())?;
+    // Only missing until the first key is stored
+    let secret = key_hash_secret_read(vault_deps.storage)?.unwrap_or_default();
+
+    for address in addresses {
+        let canonical_addr = deps.api.addr_canonicalize(address.as_str())?;
+
+        let expected_key = read_viewing_key(vault_deps.storage, &canonical_addr);
+
+        match expected_key {
+            None => {
+                // Checking the key will take significant time. We don't want to exit immediately if it isn't set
+                // in a way which will allow to time the command and determine if a viewing key doesn't exist
+                key.check_viewing_key(
+                    &[0u8; VIEWING_KEY_SIZE],
+                    KeyHashAlgorithm::HmacSha256,
+                    &secret,
+                    &contract,
+                    &canonical_addr,
+                );
+            }
+            Some(viewer)
+                if key.check_viewing_key(
+                    viewer.key_hash.as_slice(),
+                    viewer.algorithm,
+                    &secret,
+                    &contract,
+                    &canonical_addr,
+                ) =>
+            {
+                // Keys from before the last RevokeAllViewingKeys are no longer valid
+                let state = config_read(vault_deps.storage).load()?;
+                if viewer.epoch != state.key_epoch {
+                    return Err(ContractError::InvalidViewingKey);
+                }
+                if viewer.expiration.is_expired(&env.block) {
+                    return Err(ContractError::ViewingKeyExpired);
+                }
+                return perform_query(
+                    vault_deps,
+                    &env.block,
+                    msg.get_query(),
+                    &canonical_addr,
+                    &viewer.scope,
+                );
+            }
+            Some(_) => {}
+        }
+    }
+
+    Err(ContractError::InvalidViewingKey)
+}
+
+fn permit_queries(
+    deps: Deps,
+    vault_deps: Deps,
+    env: Env,
+    permit: Permit<StrongboxPermission>,
+    query: QueryWithPermit,
+) -> Result<Binary, ContractError> {
+    // Validate permit content
+    let account = validate(
+        deps,
+        PREFIX_REVOKED_PERMITS,
+        &permit,
+        env.contract.address.to_string(),
+        None,
+    )?;
+
+    if !permit.check_permission(&StrongboxPermission::Read) {
+        return Err(ContractError::MissingPermission {
+            permissions: format!("{:?}", permit.params.permissions),
+        });
+    }
+
+    let signer = deps.api.addr_canonicalize(&account)?;
+    if let QueryWithPermit::MyStrongboxes {} = query {
+        return Ok(to_binary(&query_my_strongboxes(deps, &signer)?)?);
+    }
+
+    // Owner can always read, other accounts need to be registered as viewers
+    let state = config_read(vault_deps.storage).load()?;
+    if signer == state.owner {
+        return perform_query(vault_deps, &env.block, query, &signer, &ViewerScope::All);
+    }
+
+    // Granted viewers don't need to set up a viewing key to query with a permit
+    if let Some(grant) = read_viewer_grant(vault_deps.storage, &signer)? {
+        if grant.epoch == state.key_epoch && !grant.expiration.is_expired(&env.block) {
+            return perform_query(vault_deps, &env.block, query, &signer, &grant.scope);
+        }
+    }
+
+    match read_viewing_key(vault_deps.storage, &signer) {
+        Some(viewer) if viewer.epoch != state.key_epoch => Err(ContractError::Unauthorized),
+        Some(viewer) if viewer.expiration.is_expired(&env.block) => {
+            Err(ContractError::ViewingKeyExpired)
+        }
+        Some(viewer) => perform_query(vault_deps, &env.block, query, &signer, &viewer.scope),
+        None => Err(ContractError::Unauthorized),
+    }
+}
+
+fn perform_query(
+    deps: Deps,
+    block: &BlockInfo,
+    query: QueryWithPermit,
+    account: &CanonicalAddr,
+    scope: &ViewerScope,
+) -> Result<Binary, ContractError> {
+    // Only the owner can read while the strongbox is locked down
+    let state = config_read(deps.storage).load()?;
+    let is_owner = *account == state.owner;
+    if !is_owner && matches!(state.lockdown, Some(ref lockdown) if lockdown.is_active(block)) {
+        return Err(ContractError::LockedDown);
+    }
+
+    match query {
+        QueryWithPermit::GetStrongbox { encrypt_to } => {
+            if !scope.allows_strongbox() {
+                return Err(ContractError::OutOfScope);
+            }
+            let strongbox = query_strongbox(deps)?;
+            match encrypt_to {
+                Some(public_key) => Ok(to_binary(&encrypt_response(
+                    &state,
+                    block,
+                    &public_key,
+                    &to_vec(&strongbox)?,
+                )?)?),
+                None => Ok(to_binary(&strongbox)?),
+            }
+        }
+        QueryWithPermit::GetEntry { label } => {
+            if !scope.allows_entry(&label) {
+                return Err(ContractError::OutOfScope);
+            }
+            Ok(to_binary(&query_entry(deps, &label)?)?)
+        }
+        QueryWithPermit::ListEntryLabels {} => Ok(to_binary(&query_entry_labels(deps, scope)?)?),
+        QueryWithPermit::GetPendingOwner {} => {
+            if !scope.allows_strongbox() {
+                return Err(ContractError::OutOfScope);
+            }
+            Ok(to_binary(&query_pending_owner(deps)?)?)
+        }
+        QueryWithPermit::GetRecovery {} => {
+            if !scope.allows_strongbox() {
+                return Err(ContractError::OutOfScope);
+            }
+            Ok(to_binary(&query_recovery(deps)?)?)
+        }
+        QueryWithPermit::GetGuardians {} => {
+            if !scope.allows_strongbox() {
+                return Err(ContractError::OutOfScope);
+            }
+            Ok(to_binary(&query_guardians(deps)?)?)
+        }
+        QueryWithPermit::GetStrongboxRevision { revision } => {
+            if !scope.allows_strongbox() {
+                return Err(ContractError::OutOfScope);
+            }
+            Ok(to_binary(&query_revision(deps, revision)?)?)
+        }
+        QueryWithPermit::ListRevisions { page, page_size } => {
+            if !scope.allows_strongbox() {
+                return Err(ContractError::OutOfScope);
+            }
+            Ok(to_binary(&query_revisions(deps, page, page_size)?)?)
+        }
+        // Answered by the factory before a vault is selected
+        QueryWithPermit::MyStrongboxes {} => Err(ContractError::InvalidVaultMessage),
+        QueryWithPermit::ListViewers { page, page_size } => {
+            if !is_owner {
+                return Err(ContractError::Unauthorized);
+            }
+            Ok(to_binary(&query_viewers(deps, block, page, page_size)?)?)
+        }
+        QueryWithPermit::ListRoles { page, page_size } => {
+            if !is_owner {
+                return Err(ContractError::Unauthorized);
+            }
+            Ok(to_binary(&query_roles(deps, page, page_size)?)?)
+        }
+    }
+}
+
+fn query_my_strongboxes(deps: Deps, owner: &CanonicalAddr) -> StdResult<StrongboxesResponse> {
+    Ok(StrongboxesResponse {
+        strongboxes: read_owned_strongboxes(deps.storage, owner)?,
+    })
+}
+
+fn encrypt_response(
+    state: &State,
+    block: &BlockInfo,
+    public_key: &Binary,
+    plaintext: &[u8],
+) -> Result<EncryptedResponse, ContractError> {
+    let recipient =
+        PublicKey::parse(public_key.as_slice()).map_err(|_| ContractError::InvalidPublicKey)?;
+
+    // Queries can't keep track of used nonces, so the ephemeral key and nonce are drawn from
+    // everything that sets this response apart from another one
+    let random = block.random.as_ref().map_or(&[][..], Binary::as_slice);
+    let rng_entropy = [
+        block.height.to_be_bytes().as_slice(),
+        block.time.nanos().to_be_bytes().as_slice(),
+        random,
+        public_key.as_slice(),
+        sha_256(plaintext).as_slice(),
+    ]
+    .concat();
+    let mut rng = Prng::new(&state.serenity_seed, &rng_entropy);
+
+    let ephemeral_key = PrivateKey::parse(&rng.rand_bytes())?;
+    let mut nonce = [0u8; NONCE_SIZE];
+    nonce.copy_from_slice(&rng.rand_bytes()[..NONCE_SIZE]);
+
+    ecies::encrypt(&recipient, &ephemeral_key, &nonce, plaintext)
+}
+
+fn query_strongbox(deps: Deps) -> StdResult<StrongboxResponse> {
+    let state = config_read(deps.storage).load()?;
+    let strongbox = strongbox_content_read(deps.storage).load()?;
+
+    Ok(StrongboxResponse {
+        strongbox,
+        revision: state.revision,
+    })
+}
+
+fn query_entry(deps: Deps, label: &str) -> Result<EntryResponse, ContractError> {
+    let value = read_entry(deps.storage, label).ok_or(ContractError::EntryNotFound)?;
+
+    Ok(EntryResponse {
+        label: label.to_string(),
+        value,
+    })
+}
+
+fn query_entry_labels(deps: Deps, scope: &ViewerScope) -> StdResult<EntryLabelsResponse> {
+    let labels = entry_labels_read(deps.storage)
+        .may_load()?
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|label| scope.allows_entry(label))
+        .collect();
+
+    Ok(EntryLabelsResponse { labels })
+}
+
+fn query_pending_owner(deps: Deps) -> StdResult<PendingOwnerResponse> {
+    let state = config_read(deps.storage).load()?;
+    let pending_owner = match state.pending_owner {
+        Some(pending_owner) => Some(deps.api.addr_humanize(&pending_owner)?),
+        None => None,
+    };
+
+    Ok(PendingOwnerResponse { pending_owner })
+}
+
+fn query_recovery(deps: Deps) -> StdResult<RecoveryResponse> {
+    let recovery = match recovery_read(deps.storage).may_load()? {
+        Some(current) => {
+            let guardian_set = guardians_read(deps.storage).load()?;
+            Some(RecoveryInfo {
+                new_owner: deps.api.addr_humanize(&current.new_owner)?,
+                approvals: current
+                    .approvals
+                    .iter()
+                    .map(|approval| deps.api.addr_humanize(approval))
+                    .collect::<StdResult<_>>()?,
+                executable_at: current.initiated_at + guardian_set.delay,
+            })
+        }
+        None => None,
+    };
+
+    Ok(RecoveryResponse { recovery })
+}
+
+fn query_guardians(deps: Deps) -> StdResult<GuardiansResponse> {
+    let guardians = match guardians_read(deps.storage).may_load()? {
+        Some(guardian_set) => Some(GuardiansInfo {
+            addresses: guardian_set
+                .addresses
+                .iter()
+                .map(|address| deps.api.addr_humanize(address))
+                .collect::<StdResult<_>>()?,
+            threshold: guardian_set.threshold,
+            delay: guardian_set.delay,
+        }),
+        None => None,
+    };
+
+    Ok(GuardiansResponse { guardians })
+}
+
+fn query_revision(deps: Deps, revision: u64) -> Result<RevisionResponse, ContractError> {
+    let revision = read_revision(deps.storage, revision)?.ok_or(ContractError::RevisionNotFound)?;
+
+    Ok(RevisionResponse {
+        revision: revision.revision,
+        strongbox: revision.strongbox,
+        block_height: revision.block_height,
+        block_time: revision.block_time,
+    })
+}
+
+fn query_revisions(
+    deps: Deps,
+    page: Option<u32>,
+    page_size: Option<u32>,
+) -> StdResult<RevisionsResponse> {
+    let state = config_read(deps.storage).load()?;
+    let page = page.unwrap_or(0);
+    let page_size = page_size.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+
+    let mut revisions = vec![];
+    for revision in (state.history_start..=state.revision)
+        .rev()
+        .skip(page.saturating_mul(page_size) as usize)
+        .take(page_size as usize)
+    {
+        if let Some(revision) = read_revision(deps.storage, revision)? {
+            revisions.push(RevisionInfo {
+                revision: revision.revision,
+                block_height: revision.block_height,
+                block_time: revision.block_time,
+            });
+        }
+    }
+
+    Ok(RevisionsResponse {
+        revisions,
+        total: state.revision - state.history_start + 1,
+    })
+}
+
+fn query_viewers(
+    deps: Deps,
+    block: &BlockInfo,
+    page: Option<u32>,
+    page_size: Option<u32>,
+) -> StdResult<ViewersResponse> {
+    let state = config_read(deps.storage).load()?;
+    let count = viewer_count_read(deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    let page = page.unwrap_or(0);
+    let page_size = page_size.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+
+    let mut viewers = vec![];
+    for position in (0..count)
+        .skip(page.saturating_mul(page_size) as usize)
+        .take(page_size as usize)
+    {
+        if let Some(viewer) = read_viewer(deps.storage, position)? {
+            let active = read_viewing_key(deps.storage, &viewer.address).map_or(false, |record| {
+                record.epoch == state.key_epoch && !record.expiration.is_expired(block)
+            });
+            viewers.push(ViewerInfo {
+                address: deps.api.addr_humanize(&viewer.address)?,
+                label: viewer.label,
+                created_height: viewer.created_height,
+                created_time: viewer.created_time,
+                active,
+            });
+        }
+    }
+
+    Ok(ViewersResponse {
+        viewers,
+        total: u64::from(count),
+    })
+}
+
+fn query_roles(deps: Deps, page: Option<u32>, page_size: Option<u32>) -> StdResult<RolesResponse> {
+    let holders = role_holders_read(deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    let page = page.unwrap_or(0);
+    let page_size = page_size.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+
+    let mut roles = vec![];
+    for holder in holders
+        .iter()
+        .skip(page.saturating_mul(page_size) as usize)
+        .take(page_size as usize)
+    {
+        roles.push(RoleInfo {
+            address: deps.api.addr_humanize(holder)?,
+            roles: read_roles(deps.storage, holder)?,
+        });
+    }
+
+    Ok(RolesResponse {
+        roles,
+        total: holders.len() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::client;
+    use crate::msg::StrongboxCode;
+    use crate::state::{LegacyState, ViewerRecord, CONFIG_KEY, PREFIX_VIEWING_KEY, STRONGBOX_KEY};
+    use crate::viewing_key::VIEWING_KEY_PREFIX;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{
+        from_binary, from_slice, to_vec, Api, Coin, CosmosMsg, Event, ReplyOn, SubMsgResponse,
+        SubMsgResult, Uint128,
+    };
+    use cosmwasm_storage::{singleton, to_length_prefixed, PrefixedStorage};
+
+    #[test]
+    fn proper_initialization() {
+        let mut deps = mock_dependencies();
+        let info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let msg = InstantiateMsg {
+            serenity_seed: String::from("init strongbox"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+
+        // init action will be failed due to seed length
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg);
+        assert_eq!(res.unwrap_err(), ContractError::InvalidSeedLength);
+
+        let info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        // we can just call .unwrap() to assert this was a success
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+    }
+
+    #[test]
+    fn update_strongbox() {
+        let mut deps = mock_dependencies();
+        let info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+        // not anyone can update
+        let anyone_info = mock_info(
+            "visitor1",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let update_msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: String::from("Test strongbox"),
+            expected_revision: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), anyone_info, update_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        // owner can update
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: String::from("Test strongbox"),
+            expected_revision: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+    }
+
+    #[test]
+    fn create_viewing_key() {
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        // entropy length should be fixed
+        let anyone_info = mock_info(
+            "visitor1",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("user1"))),
+            entropy: "supbro".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+
+        let res = execute(deps.as_mut(), mock_env(), anyone_info, create_vk_msg);
+        assert_eq!(res.unwrap_err(), ContractError::InvalidEntropyLength);
+
+        // only owner can create viewing key
+        let anyone_info = mock_info(
+            "visitor2",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("user1"))),
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), anyone_info, create_vk_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        // owner can create viewing key
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("user2"))),
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg).unwrap();
+        let vk = created_key(&res);
+
+        assert!(vk.as_bytes().len() > 0, "Viewing key not valid");
+
+        // owner can't use same seed for create viewing key
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("user2"))),
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg);
+        assert_eq!(res.unwrap_err(), ContractError::DuplicateEntropy);
+    }
+
+    #[test]
+    fn query_strongbox() {
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let update_msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: String::from("Test strongbox"),
+            expected_revision: None,
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, update_msg).unwrap();
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("user1"))),
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg).unwrap();
+        let vk = created_key(&res);
+
+        // other user can't use viewing key
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user2")),
+            key: vk.to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg);
+        assert_eq!(res.unwrap_err(), ContractError::InvalidViewingKey);
+
+        // correct user can use viewing key for query strongbox
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: vk.to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: StrongboxResponse = from_binary(&res).unwrap();
+        assert_eq!(res.strongbox, "Test strongbox");
+    }
+
+    #[test]
+    fn transfer_ownership() {
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "creator1",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        let owner_info = mock_info(
+            "creator1",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let update_msg = ExecuteMsg::ProposeOwnership {
+            new_owner: Addr::unchecked("creator2"),
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, update_msg).unwrap();
+
+        // Only proposed owner can accept ownership
+        let anyone_info = mock_info(
+            "creator3",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let accept_msg = ExecuteMsg::AcceptOwnership {};
+        let res = execute(deps.as_mut(), mock_env(), anyone_info, accept_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        let new_owner_info = mock_info(
+            "creator2",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let accept_msg = ExecuteMsg::AcceptOwnership {};
+        execute(deps.as_mut(), mock_env(), new_owner_info, accept_msg).unwrap();
+
+        // Old owner can't update strongbox
+        let old_owner_info = mock_info(
+            "creator1",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let update_msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: String::from("Test strongbox"),
+            expected_revision: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), old_owner_info, update_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        // New owner can update strongbox
+        let new_owner_info = mock_info(
+            "creator2",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let update_msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: String::from("Test strongbox"),
+            expected_revision: None,
+        };
+        execute(deps.as_mut(), mock_env(), new_owner_info, update_msg).unwrap();
+    }
+
+    #[test]
+    fn revoke_viewing_key() {
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let update_msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: String::from("Test strongbox"),
+            expected_revision: None,
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, update_msg).unwrap();
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("user1"))),
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg).unwrap();
+        let vk = created_key(&res);
+
+        // owner can revoke viewing key
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let revoke_msg = ExecuteMsg::RevokeViewingKey {
+            viewer: Addr::unchecked(String::from("user1")),
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, revoke_msg).unwrap();
+
+        // user can't view strongbox with revoked key
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: vk.to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg);
+        assert_eq!(res.unwrap_err(), ContractError::InvalidViewingKey);
+    }
+
+    #[test]
+    fn strongbox_entries() {
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        // not anyone can set entry
+        let anyone_info = mock_info(
+            "visitor1",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let set_msg = ExecuteMsg::SetEntry {
+            label: String::from("seed phrase"),
+            value: String::from("Test seed phrase"),
+        };
+        let res = execute(deps.as_mut(), mock_env(), anyone_info, set_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        // owner can set entries
+        for (label, value) in [
+            ("seed phrase", "Test seed phrase"),
+            ("backup codes", "Test backup codes"),
+        ] {
+            let owner_info = mock_info(
+                "creator",
+                &[Coin {
+                    denom: "earth".to_string(),
+                    amount: Uint128::new(1000),
+                }],
+            );
+            let set_msg = ExecuteMsg::SetEntry {
+                label: String::from(label),
+                value: String::from(value),
+            };
+            execute(deps.as_mut(), mock_env(), owner_info, set_msg).unwrap();
+        }
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("user1"))),
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg).unwrap();
+        let vk = created_key(&res);
+
+        // other user can't list entries
+        let query_msg = QueryMsg::ListEntryLabels {
+            behalf: Addr::unchecked(String::from("user2")),
+            key: vk.to_string(),
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg);
+        assert_eq!(res.unwrap_err(), ContractError::InvalidViewingKey);
+
+        // viewer can list entries and read a single entry
+        let query_msg = QueryMsg::ListEntryLabels {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: vk.to_string(),
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: EntryLabelsResponse = from_binary(&res).unwrap();
+        assert_eq!(res.labels, vec!["seed phrase", "backup codes"]);
+
+        let query_msg = QueryMsg::GetEntry {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: vk.to_string(),
+            label: String::from("backup codes"),
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: EntryResponse = from_binary(&res).unwrap();
+        assert_eq!(res.value, "Test backup codes");
+
+        // owner can remove entry
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let remove_msg = ExecuteMsg::RemoveEntry {
+            label: String::from("seed phrase"),
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, remove_msg).unwrap();
+
+        let query_msg = QueryMsg::GetEntry {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: vk.to_string(),
+            label: String::from("seed phrase"),
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg);
+        assert_eq!(res.unwrap_err(), ContractError::EntryNotFound);
+
+        let query_msg = QueryMsg::ListEntryLabels {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: vk.to_string(),
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: EntryLabelsResponse = from_binary(&res).unwrap();
+        assert_eq!(res.labels, vec!["backup codes"]);
+    }
+
+    #[test]
+    fn scoped_viewing_key() {
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        for (label, value) in [
+            ("seed phrase", "Test seed phrase"),
+            ("backup codes", "Test backup codes"),
+        ] {
+            let owner_info = mock_info(
+                "creator",
+                &[Coin {
+                    denom: "earth".to_string(),
+                    amount: Uint128::new(1000),
+                }],
+            );
+            let set_msg = ExecuteMsg::SetEntry {
+                label: String::from(label),
+                value: String::from(value),
+            };
+            execute(deps.as_mut(), mock_env(), owner_info, set_msg).unwrap();
+        }
+
+        // owner creates a key restricted to a single entry
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("auditor"))),
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: Some(ViewerScope::Entries(vec![String::from("backup codes")])),
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg).unwrap();
+        let vk = created_key(&res);
+
+        // scoped viewer can't query the whole strongbox
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("auditor")),
+            key: vk.to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg);
+        assert_eq!(res.unwrap_err(), ContractError::OutOfScope);
+
+        // scoped viewer can't read other entries
+        let query_msg = QueryMsg::GetEntry {
+            behalf: Addr::unchecked(String::from("auditor")),
+            key: vk.to_string(),
+            label: String::from("seed phrase"),
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg);
+        assert_eq!(res.unwrap_err(), ContractError::OutOfScope);
+
+        // scoped viewer only sees allowed entries
+        let query_msg = QueryMsg::GetEntry {
+            behalf: Addr::unchecked(String::from("auditor")),
+            key: vk.to_string(),
+            label: String::from("backup codes"),
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: EntryResponse = from_binary(&res).unwrap();
+        assert_eq!(res.value, "Test backup codes");
+
+        let query_msg = QueryMsg::ListEntryLabels {
+            behalf: Addr::unchecked(String::from("auditor")),
+            key: vk.to_string(),
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: EntryLabelsResponse = from_binary(&res).unwrap();
+        assert_eq!(res.labels, vec!["backup codes"]);
+
+        // scoped viewer can't see who the strongbox is handed to
+        let query_msg = QueryMsg::GetPendingOwner {
+            behalf: Addr::unchecked(String::from("auditor")),
+            key: vk.to_string(),
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg);
+        assert_eq!(res.unwrap_err(), ContractError::OutOfScope);
+
+        let query_msg = QueryMsg::GetRecovery {
+            behalf: Addr::unchecked(String::from("auditor")),
+            key: vk.to_string(),
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg);
+        assert_eq!(res.unwrap_err(), ContractError::OutOfScope);
+    }
+
+    #[test]
+    fn legacy_viewing_key_record() {
+        let mut deps = mock_dependencies();
+        let viewer = deps.api.addr_canonicalize("user1").unwrap();
+        let key = ViewingKey(String::from("strongbox_key_legacy"));
+
+        // keys stored before scopes existed only hold the hash
+        let mut user_key_store = PrefixedStorage::new(&mut deps.storage, PREFIX_VIEWING_KEY);
+        user_key_store.set(viewer.as_slice(), &sha_256(key.as_bytes()));
+
+        let contract = deps
+            .api
+            .addr_canonicalize(mock_env().contract.address.as_str())
+            .unwrap();
+        let record = read_viewing_key(&deps.storage, &viewer).unwrap();
+        assert_eq!(record.algorithm, KeyHashAlgorithm::Sha256);
+        assert!(key.check_viewing_key(
+            record.key_hash.as_slice(),
+            record.algorithm,
+            &[],
+            &contract,
+            &viewer
+        ));
+        assert_eq!(record.scope, ViewerScope::All);
+        assert_eq!(record.expiration, Expiration::default());
+    }
+
+    #[test]
+    fn expiring_viewing_key() {
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        // owner can't create an already expired key
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("user1"))),
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: Some(mock_env().block.height),
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg);
+        assert_eq!(res.unwrap_err(), ContractError::InvalidExpiration);
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("user1"))),
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: Some(mock_env().block.time.seconds() + 3600),
+            expires_at_height: Some(mock_env().block.height + 10),
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg).unwrap();
+        let vk = created_key(&res);
+
+        // key works before expiration
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: vk.to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        query(deps.as_ref(), mock_env(), query_msg).unwrap();
+
+        // key stops working once the height bound is reached
+        let mut env = mock_env();
+        env.block.height += 10;
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: vk.to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        let res = query(deps.as_ref(), env, query_msg);
+        assert_eq!(res.unwrap_err(), ContractError::ViewingKeyExpired);
+
+        // key stops working once the time bound is reached
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(3600);
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: vk.to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        let res = query(deps.as_ref(), env, query_msg);
+        assert_eq!(res.unwrap_err(), ContractError::ViewingKeyExpired);
+    }
+
+    fn created_key(res: &Response) -> ViewingKey {
+        match from_binary(res.data.as_ref().unwrap()).unwrap() {
+            ExecuteAnswer::CreateViewingKey(ViewingKeyResponse { key }) => key,
+            _ => panic!("Unexpected result from handle"),
+        }
+    }
+
+    fn test_permit(pub_key: &str, signature: &str) -> Permit<StrongboxPermission> {
+        from_slice(
+            format!(
+                r#"{{
+                    "params": {{
+                        "permit_name": "strongbox",
+                        "allowed_tokens": ["cosmos2contract"],
+                        "chain_id": "secret-4",
+                        "permissions": ["read"]
+                    }},
+                    "signature": {{
+                        "pub_key": {{
+                            "type": "tendermint/PubKeySecp256k1",
+                            "value": "{}"
+                        }},
+                        "signature": "{}"
+                    }}
+                }}"#,
+                pub_key, signature
+            )
+            .as_bytes(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn query_with_permit() {
+        // secret1297ctp7t8d2dfm0zdxp4umjjs746s4a5zk34ss
+        let owner_permit = test_permit(
+            "A+4qBrExF0O+FjupvQncS0AhfiY47cjQTR0k8gHSdY8a",
+            "7mcDHV7JFhGjw+9J6qRC6zyncr9sg7LXVbnZV3a9aggUUuchgx+pVX6yLwrL8mXlEwbmW8c44tywwRjsIJl5Aw==",
+        );
+        // secret1d82n5kh9tg0fd3f89h23hadegestqlry3ehzly
+        let viewer_permit = test_permit(
+            "AmPF6gohPVOQqGUPwx8CSQFDhrGssGZVXpasNl7Hrc1M",
+            "2i8I3PytQS3Gft5qi4pvMKtsTAyYjmdc9kjKx7Q1LypB1LWetjSB9D1BBdZacAzxLMmrfixl8Uoc2KHaGIfj3g==",
+        );
+
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "secret1297ctp7t8d2dfm0zdxp4umjjs746s4a5zk34ss",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        let owner_info = mock_info(
+            "secret1297ctp7t8d2dfm0zdxp4umjjs746s4a5zk34ss",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let update_msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: String::from("Test strongbox"),
+            expected_revision: None,
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, update_msg).unwrap();
+
+        // owner can query with permit
+        let query_msg = QueryMsg::WithPermit {
+            permit: owner_permit,
+            query: QueryWithPermit::GetStrongbox { encrypt_to: None },
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: StrongboxResponse = from_binary(&res).unwrap();
+        assert_eq!(res.strongbox, "Test strongbox");
+
+        // unregistered account can't query with permit
+        let query_msg = QueryMsg::WithPermit {
+            permit: viewer_permit.clone(),
+            query: QueryWithPermit::GetStrongbox { encrypt_to: None },
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        // registered viewer can query with permit
+        let owner_info = mock_info(
+            "secret1297ctp7t8d2dfm0zdxp4umjjs746s4a5zk34ss",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from(
+                "secret1d82n5kh9tg0fd3f89h23hadegestqlry3ehzly",
+            ))),
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg).unwrap();
+
+        let query_msg = QueryMsg::WithPermit {
+            permit: viewer_permit.clone(),
+            query: QueryWithPermit::GetStrongbox { encrypt_to: None },
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: StrongboxResponse = from_binary(&res).unwrap();
+        assert_eq!(res.strongbox, "Test strongbox");
+
+        // revoked permit can't be used anymore
+        let viewer_info = mock_info(
+            "secret1d82n5kh9tg0fd3f89h23hadegestqlry3ehzly",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let revoke_msg = ExecuteMsg::RevokePermit {
+            name: String::from("strongbox"),
+        };
+        execute(deps.as_mut(), mock_env(), viewer_info, revoke_msg).unwrap();
+
+        let query_msg = QueryMsg::WithPermit {
+            permit: viewer_permit,
+            query: QueryWithPermit::GetStrongbox { encrypt_to: None },
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg);
+        assert!(res.is_err(), "Revoked permit should be rejected");
+    }
+
+    #[test]
+    fn cancel_ownership_proposal() {
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "creator1",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        let owner_info = mock_info(
+            "creator1",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let propose_msg = ExecuteMsg::ProposeOwnership {
+            new_owner: Addr::unchecked("creator2"),
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, propose_msg).unwrap();
+
+        let owner_info = mock_info(
+            "creator1",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("user1"))),
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg).unwrap();
+        let vk = created_key(&res);
+
+        // viewer can see the pending owner
+        let query_msg = QueryMsg::GetPendingOwner {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: vk.to_string(),
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: PendingOwnerResponse = from_binary(&res).unwrap();
+        assert_eq!(res.pending_owner, Some(Addr::unchecked("creator2")));
+
+        // only owner can cancel the proposal
+        let anyone_info = mock_info(
+            "creator2",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let cancel_msg = ExecuteMsg::CancelOwnershipProposal {};
+        let res = execute(deps.as_mut(), mock_env(), anyone_info, cancel_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        let owner_info = mock_info(
+            "creator1",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let cancel_msg = ExecuteMsg::CancelOwnershipProposal {};
+        execute(deps.as_mut(), mock_env(), owner_info, cancel_msg).unwrap();
+
+        // cancelled proposal can't be accepted
+        let new_owner_info = mock_info(
+            "creator2",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let accept_msg = ExecuteMsg::AcceptOwnership {};
+        let res = execute(deps.as_mut(), mock_env(), new_owner_info, accept_msg);
+        assert_eq!(res.unwrap_err(), ContractError::OwnershipProposalNotFound);
+
+        let query_msg = QueryMsg::GetPendingOwner {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: vk.to_string(),
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: PendingOwnerResponse = from_binary(&res).unwrap();
+        assert_eq!(res.pending_owner, None);
+    }
+
+    #[test]
+    fn migrate_v0_1_0_state() {
+        let mut deps = mock_dependencies();
+        let owner = deps.api.addr_canonicalize("creator").unwrap();
+        let viewer = deps.api.addr_canonicalize("user1").unwrap();
+        let key = ViewingKey(String::from("strongbox_key_legacy"));
+
+        // v0.1.0 stored the state without pending owner and no contract version
+        let legacy = LegacyState {
+            owner: owner.clone(),
+            strongbox: String::from("Test strongbox"),
+            serenity_seed: sha_256(b"r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh").to_vec(),
+            entropy_hashes: vec![to_binary(&sha_256(b"2418D8fZhQs8jIzuhiZ8")).unwrap()],
+        };
+        singleton(&mut deps.storage, CONFIG_KEY)
+            .save(&legacy)
+            .unwrap();
+        let mut user_key_store = PrefixedStorage::new(&mut deps.storage, PREFIX_VIEWING_KEY);
+        user_key_store.set(viewer.as_slice(), &sha_256(key.as_bytes()));
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+        let state = config_read(&deps.storage).load().unwrap();
+        assert_eq!(
+            state,
+            State {
+                owner,
+                serenity_seed: legacy.serenity_seed,
+                pending_owner: None,
+                revision: 0,
+                history_start: 0,
+                history_depth: DEFAULT_HISTORY_DEPTH,
+                key_epoch: 0,
+                lockdown: None,
+            }
+        );
+
+        let version = contract_version_read(&deps.storage).load().unwrap();
+        assert_eq!(version.contract, CONTRACT_NAME);
+        assert_eq!(version.version, CONTRACT_VERSION);
+
+        // existing viewing keys keep working after migration
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: key.to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: StrongboxResponse = from_binary(&res).unwrap();
+        assert_eq!(res.strongbox, "Test strongbox");
+
+        // existing strongbox becomes the first revision
+        let query_msg = QueryMsg::GetStrongboxRevision {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: key.to_string(),
+            revision: 0,
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: RevisionResponse = from_binary(&res).unwrap();
+        assert_eq!(res.strongbox, "Test strongbox");
+
+        // existing entropy is still rejected
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("user2"))),
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg);
+        assert_eq!(res.unwrap_err(), ContractError::DuplicateEntropy);
+
+        // other contracts can't be migrated into strongbox
+        contract_version(&mut deps.storage)
+            .save(&ContractVersion {
+                contract: String::from("crates.io:other_contract"),
+                version: String::from("1.0.0"),
+            })
+            .unwrap();
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {});
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::InvalidMigration {
+                contract: String::from("crates.io:other_contract"),
+            }
+        );
+
+        // a newer version can't be migrated down
+        contract_version(&mut deps.storage)
+            .save(&ContractVersion {
+                contract: CONTRACT_NAME.to_string(),
+                version: String::from("99.0.0"),
+            })
+            .unwrap();
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {});
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::InvalidMigrationVersion {
+                version: String::from("99.0.0"),
+            }
+        );
+    }
+
+    #[test]
+    fn strongbox_history() {
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        // keep two prior revisions besides the current one
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let depth_msg = ExecuteMsg::SetHistoryDepth { depth: 2 };
+        execute(deps.as_mut(), mock_env(), owner_info, depth_msg).unwrap();
+
+        for (i, strongbox) in ["Strongbox #1", "Strongbox #2", "Strongbox #3"]
+            .iter()
+            .enumerate()
+        {
+            let owner_info = mock_info(
+                "creator",
+                &[Coin {
+                    denom: "earth".to_string(),
+                    amount: Uint128::new(1000),
+                }],
+            );
+            let mut env = mock_env();
+            env.block.height += i as u64 + 1;
+            let update_msg = ExecuteMsg::UpdateStrongbox {
+                strongbox: String::from(*strongbox),
+                expected_revision: None,
+            };
+            execute(deps.as_mut(), env, owner_info, update_msg).unwrap();
+        }
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("user1"))),
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg).unwrap();
+        let vk = created_key(&res);
+
+        // oldest revisions beyond the depth are dropped
+        let query_msg = QueryMsg::ListRevisions {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: vk.to_string(),
+            page: None,
+            page_size: None,
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: RevisionsResponse = from_binary(&res).unwrap();
+        assert_eq!(res.total, 3);
+        assert_eq!(
+            res.revisions
+                .iter()
+                .map(|x| x.revision)
+                .collect::<Vec<u64>>(),
+            vec![3, 2, 1]
+        );
+        assert_eq!(res.revisions[0].block_height, mock_env().block.height + 3);
+
+        let query_msg = QueryMsg::GetStrongboxRevision {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: vk.to_string(),
+            revision: 0,
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg);
+        assert_eq!(res.unwrap_err(), ContractError::RevisionNotFound);
+
+        let query_msg = QueryMsg::GetStrongboxRevision {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: vk.to_string(),
+            revision: 1,
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: RevisionResponse = from_binary(&res).unwrap();
+        assert_eq!(res.strongbox, "Strongbox #1");
+
+        // not anyone can restore a revision
+        let anyone_info = mock_info(
+            "visitor1",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let restore_msg = ExecuteMsg::RestoreRevision { revision: 1 };
+        let res = execute(deps.as_mut(), mock_env(), anyone_info, restore_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        // owner can restore a revision as a new revision
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let restore_msg = ExecuteMsg::RestoreRevision { revision: 1 };
+        execute(deps.as_mut(), mock_env(), owner_info, restore_msg).unwrap();
+
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: vk.to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: StrongboxResponse = from_binary(&res).unwrap();
+        assert_eq!(res.strongbox, "Strongbox #1");
+
+        let query_msg = QueryMsg::ListRevisions {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: vk.to_string(),
+            page: Some(1),
+            page_size: Some(2),
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: RevisionsResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            res.revisions
+                .iter()
+                .map(|x| x.revision)
+                .collect::<Vec<u64>>(),
+            vec![2]
+        );
+    }
+
+    #[test]
+    fn update_strongbox_expected_revision() {
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        // first device updates from the revision it read
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let update_msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: String::from("Strongbox from device #1"),
+            expected_revision: Some(0),
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, update_msg).unwrap();
+
+        // second device still holds the old revision and is rejected
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let update_msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: String::from("Strongbox from device #2"),
+            expected_revision: Some(0),
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, update_msg);
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::RevisionMismatch {
+                expected: 0,
+                actual: 1,
+            }
+        );
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("user1"))),
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg).unwrap();
+        let vk = created_key(&res);
+
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: vk.to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: StrongboxResponse = from_binary(&res).unwrap();
+        assert_eq!(res.strongbox, "Strongbox from device #1");
+        assert_eq!(res.revision, 1);
+    }
+
+    #[test]
+    fn viewer_sets_own_viewing_key() {
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let update_msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: String::from("Test strongbox"),
+            expected_revision: None,
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, update_msg).unwrap();
+
+        // viewer without grant can't set a key
+        let viewer_info = mock_info(
+            "user1",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let set_vk_msg = ExecuteMsg::SetViewingKey {
+            key: String::from("my own key"),
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), viewer_info, set_vk_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        // only owner can grant viewer rights
+        let anyone_info = mock_info(
+            "user1",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let grant_msg = ExecuteMsg::GrantViewer {
+            viewer: Addr::unchecked(String::from("user1")),
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), anyone_info, grant_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let grant_msg = ExecuteMsg::GrantViewer {
+            viewer: Addr::unchecked(String::from("user1")),
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, grant_msg).unwrap();
+
+        // granted viewer sets their own key
+        let viewer_info = mock_info(
+            "user1",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let set_vk_msg = ExecuteMsg::SetViewingKey {
+            key: String::from("my own key"),
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), viewer_info, set_vk_msg).unwrap();
+        let answer: ExecuteAnswer = from_binary(&res.data.unwrap()).unwrap();
+        assert_eq!(
+            answer,
+            ExecuteAnswer::SetViewingKey {
+                status: ResponseStatus::Success
+            }
+        );
+
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: String::from("my own key"),
+            vault: None,
+            encrypt_to: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: StrongboxResponse = from_binary(&res).unwrap();
+        assert_eq!(res.strongbox, "Test strongbox");
+
+        // granted viewer creates their own key from entropy
+        let viewer_info = mock_info(
+            "user1",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: None,
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), viewer_info, create_vk_msg).unwrap();
+        let vk = created_key(&res);
+
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: vk.to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: StrongboxResponse = from_binary(&res).unwrap();
+        assert_eq!(res.strongbox, "Test strongbox");
+
+        // revoked grant can't be used to set keys anymore
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let revoke_msg = ExecuteMsg::RevokeViewerGrant {
+            viewer: Addr::unchecked(String::from("user1")),
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, revoke_msg).unwrap();
+
+        let viewer_info = mock_info(
+            "user1",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let set_vk_msg = ExecuteMsg::SetViewingKey {
+            key: String::from("another key"),
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), viewer_info, set_vk_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let revoke_msg = ExecuteMsg::RevokeViewerGrant {
+            viewer: Addr::unchecked(String::from("user1")),
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, revoke_msg);
+        assert_eq!(res.unwrap_err(), ContractError::GrantNotFound);
+    }
+
+    #[test]
+    fn revoked_viewer_loses_grant() {
+        let owner = "secret1297ctp7t8d2dfm0zdxp4umjjs746s4a5zk34ss";
+        let viewer = "secret1d82n5kh9tg0fd3f89h23hadegestqlry3ehzly";
+        let viewer_permit = test_permit(
+            "AmPF6gohPVOQqGUPwx8CSQFDhrGssGZVXpasNl7Hrc1M",
+            "2i8I3PytQS3Gft5qi4pvMKtsTAyYjmdc9kjKx7Q1LypB1LWetjSB9D1BBdZacAzxLMmrfixl8Uoc2KHaGIfj3g==",
+        );
+
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            owner,
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        let owner_info = mock_info(
+            owner,
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let update_msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: String::from("Test strongbox"),
+            expected_revision: None,
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, update_msg).unwrap();
+
+        let owner_info = mock_info(
+            owner,
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let grant_msg = ExecuteMsg::GrantViewer {
+            viewer: Addr::unchecked(String::from(viewer)),
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, grant_msg).unwrap();
+
+        // granted viewer queries with a permit without setting up a key
+        let query_msg = QueryMsg::WithPermit {
+            permit: viewer_permit.clone(),
+            query: QueryWithPermit::GetStrongbox { encrypt_to: None },
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: StrongboxResponse = from_binary(&res).unwrap();
+        assert_eq!(res.strongbox, "Test strongbox");
+
+        let viewer_info = mock_info(
+            viewer,
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let set_vk_msg = ExecuteMsg::SetViewingKey {
+            key: String::from("my own key"),
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), viewer_info, set_vk_msg).unwrap();
+
+        // revoking the key withdraws the grant
+        let owner_info = mock_info(
+            owner,
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let revoke_msg = ExecuteMsg::RevokeViewingKey {
+            viewer: Addr::unchecked(String::from(viewer)),
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, revoke_msg).unwrap();
+
+        let viewer_info = mock_info(
+            viewer,
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let set_vk_msg = ExecuteMsg::SetViewingKey {
+            key: String::from("another key"),
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), viewer_info, set_vk_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        let query_msg = QueryMsg::WithPermit {
+            permit: viewer_permit.clone(),
+            query: QueryWithPermit::GetStrongbox { encrypt_to: None },
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        // revoking all keys withdraws grants made before
+        let owner_info = mock_info(
+            owner,
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let grant_msg = ExecuteMsg::GrantViewer {
+            viewer: Addr::unchecked(String::from(viewer)),
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, grant_msg).unwrap();
+
+        let owner_info = mock_info(
+            owner,
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info,
+            ExecuteMsg::RevokeAllViewingKeys {},
+        )
+        .unwrap();
+
+        let viewer_info = mock_info(
+            viewer,
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let set_vk_msg = ExecuteMsg::SetViewingKey {
+            key: String::from("another key"),
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), viewer_info, set_vk_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        let query_msg = QueryMsg::WithPermit {
+            permit: viewer_permit.clone(),
+            query: QueryWithPermit::GetStrongbox { encrypt_to: None },
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        // a new grant works again
+        let owner_info = mock_info(
+            owner,
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let grant_msg = ExecuteMsg::GrantViewer {
+            viewer: Addr::unchecked(String::from(viewer)),
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, grant_msg).unwrap();
+
+        let query_msg = QueryMsg::WithPermit {
+            permit: viewer_permit,
+            query: QueryWithPermit::GetStrongbox { encrypt_to: None },
+            vault: None,
+        };
+        query(deps.as_ref(), mock_env(), query_msg).unwrap();
+    }
+
+    #[test]
+    fn list_viewers() {
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        // owner creates keys for itself and two viewers, the last one expiring
+        let mut keys = vec![];
+        for (viewer, entropy, label, expires_at_height) in [
+            ("creator", "2418D8fZhQs8jIzuhiZ8", None, None),
+            (
+                "user1",
+                "9rT2kLw8xQp4vN6bZc1M",
+                Some(String::from("accountant")),
+                None,
+            ),
+            (
+                "user2",
+                "Hq7sY3nB5mK0pW2eR9tA",
+                None,
+                Some(mock_env().block.height + 10),
+            ),
+        ] {
+            let owner_info = mock_info(
+                "creator",
+                &[Coin {
+                    denom: "earth".to_string(),
+                    amount: Uint128::new(1000),
+                }],
+            );
+            let create_vk_msg = ExecuteMsg::CreateViewingKey {
+                viewer: Some(Addr::unchecked(String::from(viewer))),
+                entropy: entropy.to_string(),
+                entropy_bytes: None,
+                scope: None,
+                expires_at_time: None,
+                expires_at_height,
+                label,
+                padding: None,
+            };
+            let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg).unwrap();
+            let vk = created_key(&res);
+            keys.push(vk);
+        }
+
+        // owner lists every viewer with their own key
+        let query_msg = QueryMsg::ListViewers {
+            behalf: Addr::unchecked(String::from("creator")),
+            key: keys[0].to_string(),
+            page: None,
+            page_size: None,
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: ViewersResponse = from_binary(&res).unwrap();
+        assert_eq!(res.total, 3);
+        assert_eq!(
+            res.viewers[1],
+            ViewerInfo {
+                address: Addr::unchecked(String::from("user1")),
+                label: Some(String::from("accountant")),
+                created_height: mock_env().block.height,
+                created_time: mock_env().block.time.seconds(),
+                active: true,
+            }
+        );
+
+        let query_msg = QueryMsg::ListViewers {
+            behalf: Addr::unchecked(String::from("creator")),
+            key: keys[0].to_string(),
+            page: Some(1),
+            page_size: Some(2),
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: ViewersResponse = from_binary(&res).unwrap();
+        assert_eq!(res.viewers.len(), 1);
+        assert_eq!(
+            res.viewers[0].address,
+            Addr::unchecked(String::from("user2"))
+        );
+        assert!(res.viewers[0].active);
+
+        // expired keys are listed as inactive
+        let mut env = mock_env();
+        env.block.height += 10;
+        let query_msg = QueryMsg::ListViewers {
+            behalf: Addr::unchecked(String::from("creator")),
+            key: keys[0].to_string(),
+            page: Some(1),
+            page_size: Some(2),
+            vault: None,
+        };
+        let res = query(deps.as_ref(), env, query_msg).unwrap();
+        let res: ViewersResponse = from_binary(&res).unwrap();
+        assert!(!res.viewers[0].active);
+
+        // viewers can't list other viewers
+        let query_msg = QueryMsg::ListViewers {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: keys[1].to_string(),
+            page: None,
+            page_size: None,
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        // revoked viewer is dropped from the list
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let revoke_msg = ExecuteMsg::RevokeViewingKey {
+            viewer: Addr::unchecked(String::from("user1")),
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, revoke_msg).unwrap();
+
+        let query_msg = QueryMsg::ListViewers {
+            behalf: Addr::unchecked(String::from("creator")),
+            key: keys[0].to_string(),
+            page: None,
+            page_size: None,
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: ViewersResponse = from_binary(&res).unwrap();
+        assert_eq!(res.total, 2);
+        let addresses: Vec<Addr> = res.viewers.into_iter().map(|x| x.address).collect();
+        assert_eq!(
+            addresses,
+            vec![
+                Addr::unchecked(String::from("creator")),
+                Addr::unchecked(String::from("user2"))
+            ]
+        );
+    }
+
+    #[test]
+    fn revoke_all_viewing_keys() {
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("user1"))),
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg).unwrap();
+        let old_vk = created_key(&res);
+
+        // only owner can revoke all viewing keys
+        let visitor_info = mock_info(
+            "user1",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            visitor_info,
+            ExecuteMsg::RevokeAllViewingKeys {},
+        );
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info,
+            ExecuteMsg::RevokeAllViewingKeys {},
+        )
+        .unwrap();
+
+        // old key doesn't work anymore
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: old_vk.to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg);
+        assert_eq!(res.unwrap_err(), ContractError::InvalidViewingKey);
+
+        // newly created key works
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("user1"))),
+            entropy: "9rT2kLw8xQp4vN6bZc1M".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg).unwrap();
+        let new_vk = created_key(&res);
+
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: new_vk.to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        query(deps.as_ref(), mock_env(), query_msg).unwrap();
+    }
+
+    #[test]
+    fn lockdown() {
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        let mut keys = vec![];
+        for (viewer, entropy) in [
+            ("creator", "2418D8fZhQs8jIzuhiZ8"),
+            ("user1", "9rT2kLw8xQp4vN6bZc1M"),
+        ] {
+            let owner_info = mock_info(
+                "creator",
+                &[Coin {
+                    denom: "earth".to_string(),
+                    amount: Uint128::new(1000),
+                }],
+            );
+            let create_vk_msg = ExecuteMsg::CreateViewingKey {
+                viewer: Some(Addr::unchecked(String::from(viewer))),
+                entropy: entropy.to_string(),
+                entropy_bytes: None,
+                scope: None,
+                expires_at_time: None,
+                expires_at_height: None,
+                label: None,
+                padding: None,
+            };
+            let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg).unwrap();
+            let vk = created_key(&res);
+            keys.push(vk);
+        }
+
+        // lockdown must end in the future
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let lockdown_msg = ExecuteMsg::Lockdown {
+            until: Some(mock_env().block.time.seconds()),
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, lockdown_msg);
+        assert_eq!(res.unwrap_err(), ContractError::InvalidExpiration);
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let lockdown_msg = ExecuteMsg::Lockdown {
+            until: Some(mock_env().block.time.seconds() + 3600),
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, lockdown_msg).unwrap();
+
+        // viewers are rejected, owner can still read
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: keys[1].to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg);
+        assert_eq!(res.unwrap_err(), ContractError::LockedDown);
+
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("creator")),
+            key: keys[0].to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        query(deps.as_ref(), mock_env(), query_msg).unwrap();
+
+        // lockdown lifts by itself once until is reached
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(3600);
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: keys[1].to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        query(deps.as_ref(), env, query_msg).unwrap();
+
+        // owner lifts an open-ended lockdown
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let lockdown_msg = ExecuteMsg::Lockdown { until: None };
+        execute(deps.as_mut(), mock_env(), owner_info, lockdown_msg).unwrap();
+
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: keys[1].to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg);
+        assert_eq!(res.unwrap_err(), ContractError::LockedDown);
+
+        let visitor_info = mock_info(
+            "user1",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            visitor_info,
+            ExecuteMsg::LiftLockdown {},
+        );
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info,
+            ExecuteMsg::LiftLockdown {},
+        )
+        .unwrap();
+
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: keys[1].to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        query(deps.as_ref(), mock_env(), query_msg).unwrap();
+    }
+
+    #[test]
+    fn migrate_v0_2_0_state() {
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        // 0.2.0 kept the strongbox and entropy hashes inside the state
+        let state = config_read(&deps.storage).load().unwrap();
+        let entropy_hashes = vec![to_binary(&sha_256(b"2418D8fZhQs8jIzuhiZ8")).unwrap()];
+        let mut raw = String::from_utf8(to_vec(&state).unwrap()).unwrap();
+        raw.pop();
+        raw.push_str(&format!(
+            ",\"strongbox\":\"Test strongbox\",\"entropy_hashes\":{}}}",
+            String::from_utf8(to_vec(&entropy_hashes).unwrap()).unwrap()
+        ));
+        deps.storage
+            .set(&to_length_prefixed(CONFIG_KEY), raw.as_bytes());
+        deps.storage.remove(&to_length_prefixed(STRONGBOX_KEY));
+        contract_version(&mut deps.storage)
+            .save(&ContractVersion {
+                contract: CONTRACT_NAME.to_string(),
+                version: String::from("0.2.0"),
+            })
+            .unwrap();
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(
+            contract_version_read(&deps.storage).load().unwrap().version,
+            CONTRACT_VERSION
+        );
+
+        // moved fields are no longer part of the state
+        let raw =
+            String::from_utf8(deps.storage.get(&to_length_prefixed(CONFIG_KEY)).unwrap()).unwrap();
+        assert!(!raw.contains("entropy_hashes"));
+        assert!(!raw.contains("Test strongbox"));
+        assert_eq!(config_read(&deps.storage).load().unwrap(), state);
+        assert_eq!(
+            strongbox_content_read(&deps.storage).load().unwrap(),
+            "Test strongbox"
+        );
+
+        // existing entropy is still rejected
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("user1"))),
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg);
+        assert_eq!(res.unwrap_err(), ContractError::DuplicateEntropy);
+    }
+
+    #[test]
+    fn authorization_skips_strongbox() {
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let strongbox = "Large strongbox payload ".repeat(2048);
+        let update_msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: strongbox.clone(),
+            expected_revision: None,
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, update_msg).unwrap();
+
+        // state loaded for authorization stays small
+        let raw = deps.storage.get(&to_length_prefixed(CONFIG_KEY)).unwrap();
+        assert!(raw.len() < 1024);
+        assert!(!String::from_utf8(raw)
+            .unwrap()
+            .contains("Large strongbox payload"));
+
+        // corrupt the strongbox so any path reading it would fail
+        deps.storage
+            .set(&to_length_prefixed(STRONGBOX_KEY), b"corrupted");
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("user1"))),
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg).unwrap();
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let revoke_msg = ExecuteMsg::RevokeViewingKey {
+            viewer: Addr::unchecked(String::from("user1")),
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, revoke_msg).unwrap();
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let propose_msg = ExecuteMsg::ProposeOwnership {
+            new_owner: Addr::unchecked(String::from("user2")),
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, propose_msg).unwrap();
+
+        let new_owner_info = mock_info(
+            "user2",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            new_owner_info,
+            ExecuteMsg::AcceptOwnership {},
+        )
+        .unwrap();
+
+        // only reading the strongbox itself touches it
+        let new_owner_info = mock_info(
+            "user2",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("user2"))),
+            entropy: "9rT2kLw8xQp4vN6bZc1M".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), new_owner_info, create_vk_msg).unwrap();
+        let vk = created_key(&res);
+
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user2")),
+            key: vk.to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        assert!(query(deps.as_ref(), mock_env(), query_msg).is_err());
+    }
+
+    #[test]
+    fn multi_tenant_vaults() {
+        let mut deps = mock_dependencies();
+        let admin_info = mock_info(
+            "admin",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: Some(true),
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), admin_info, init_msg).unwrap();
+
+        // accounts without a vault can't update a strongbox
+        let user1_info = mock_info(
+            "user1",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let update_msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: String::from("User1 strongbox"),
+            expected_revision: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), user1_info, update_msg);
+        assert_eq!(res.unwrap_err(), ContractError::VaultNotFound);
+
+        // every user creates and updates their own vault
+        for (user, strongbox) in [("user1", "User1 strongbox"), ("user2", "User2 strongbox")] {
+            let user_info = mock_info(
+                user,
+                &[Coin {
+                    denom: "earth".to_string(),
+                    amount: Uint128::new(1000),
+                }],
+            );
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                user_info,
+                ExecuteMsg::CreateStrongbox {},
+            )
+            .unwrap();
+
+            let user_info = mock_info(
+                user,
+                &[Coin {
+                    denom: "earth".to_string(),
+                    amount: Uint128::new(1000),
+                }],
+            );
+            let update_msg = ExecuteMsg::UpdateStrongbox {
+                strongbox: String::from(strongbox),
+                expected_revision: None,
+            };
+            execute(deps.as_mut(), mock_env(), user_info, update_msg).unwrap();
+        }
+
+        let user1_info = mock_info(
+            "user1",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            user1_info,
+            ExecuteMsg::CreateStrongbox {},
+        );
+        assert_eq!(res.unwrap_err(), ContractError::VaultExists);
+
+        // viewing keys are scoped to the vault they were created in
+        let user1_info = mock_info(
+            "user1",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("viewer"))),
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), user1_info, create_vk_msg).unwrap();
+        let vk = created_key(&res);
+
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("viewer")),
+            key: vk.to_string(),
+            vault: Some(Addr::unchecked(String::from("user1"))),
+            encrypt_to: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: StrongboxResponse = from_binary(&res).unwrap();
+        assert_eq!(res.strongbox, "User1 strongbox");
+
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("viewer")),
+            key: vk.to_string(),
+            vault: Some(Addr::unchecked(String::from("user2"))),
+            encrypt_to: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg);
+        assert_eq!(res.unwrap_err(), ContractError::InvalidViewingKey);
+
+        // queries must name a vault
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("viewer")),
+            key: vk.to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg);
+        assert_eq!(res.unwrap_err(), ContractError::VaultNotFound);
+
+        // other users can't update a vault they don't own
+        let user2_info = mock_info(
+            "user2",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let update_msg = ExecuteMsg::WithVault {
+            vault: Addr::unchecked(String::from("user1")),
+            msg: Box::new(ExecuteMsg::UpdateStrongbox {
+                strongbox: String::from("Overwritten strongbox"),
+                expected_revision: None,
+            }),
+        };
+        let res = execute(deps.as_mut(), mock_env(), user2_info, update_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        // ownership of a vault is transferred within that vault
+        let user1_info = mock_info(
+            "user1",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let propose_msg = ExecuteMsg::ProposeOwnership {
+            new_owner: Addr::unchecked(String::from("user3")),
+        };
+        execute(deps.as_mut(), mock_env(), user1_info, propose_msg).unwrap();
+
+        let user3_info = mock_info(
+            "user3",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let accept_msg = ExecuteMsg::WithVault {
+            vault: Addr::unchecked(String::from("user1")),
+            msg: Box::new(ExecuteMsg::AcceptOwnership {}),
+        };
+        execute(deps.as_mut(), mock_env(), user3_info, accept_msg).unwrap();
+
+        let user3_info = mock_info(
+            "user3",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let update_msg = ExecuteMsg::WithVault {
+            vault: Addr::unchecked(String::from("user1")),
+            msg: Box::new(ExecuteMsg::UpdateStrongbox {
+                strongbox: String::from("User3 strongbox"),
+                expected_revision: None,
+            }),
+        };
+        execute(deps.as_mut(), mock_env(), user3_info, update_msg).unwrap();
+
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("viewer")),
+            key: vk.to_string(),
+            vault: Some(Addr::unchecked(String::from("user1"))),
+            encrypt_to: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: StrongboxResponse = from_binary(&res).unwrap();
+        assert_eq!(res.strongbox, "User3 strongbox");
+    }
+
+    #[test]
+    fn single_tenant_rejects_vaults() {
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        let user_info = mock_info(
+            "user1",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            user_info,
+            ExecuteMsg::CreateStrongbox {},
+        );
+        assert_eq!(res.unwrap_err(), ContractError::NotMultiTenant);
+
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: String::from("key"),
+            vault: Some(Addr::unchecked(String::from("user1"))),
+            encrypt_to: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg);
+        assert_eq!(res.unwrap_err(), ContractError::NotMultiTenant);
+    }
+
+    #[test]
+    fn factory_instantiates_strongboxes() {
+        // secret1297ctp7t8d2dfm0zdxp4umjjs746s4a5zk34ss
+        let user_permit = test_permit(
+            "A+4qBrExF0O+FjupvQncS0AhfiY47cjQTR0k8gHSdY8a",
+            "7mcDHV7JFhGjw+9J6qRC6zyncr9sg7LXVbnZV3a9aggUUuchgx+pVX6yLwrL8mXlEwbmW8c44tywwRjsIJl5Aw==",
+        );
+
+        let mut deps = mock_dependencies();
+        let admin_info = mock_info(
+            "admin",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: Some(StrongboxCode {
+                code_id: 7,
+                code_hash: String::from("strongbox_code_hash"),
+            }),
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), admin_info, init_msg).unwrap();
+
+        // factory instantiates a strongbox owned by the sender
+        let user_info = mock_info(
+            "secret1297ctp7t8d2dfm0zdxp4umjjs746s4a5zk34ss",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            user_info,
+            ExecuteMsg::CreateStrongbox {},
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].id, INSTANTIATE_STRONGBOX_REPLY_ID);
+        assert_eq!(res.messages[0].reply_on, ReplyOn::Success);
+        match &res.messages[0].msg {
+            CosmosMsg::Wasm(WasmMsg::Instantiate {
+                admin,
+                code_id,
+                msg,
+                ..
+            }) => {
+                assert_eq!(*code_id, 7);
+                assert_eq!(
+                    admin.as_deref(),
+                    Some("secret1297ctp7t8d2dfm0zdxp4umjjs746s4a5zk34ss")
+                );
+                let init_msg: InstantiateMsg = from_binary(msg).unwrap();
+                assert_eq!(init_msg.serenity_seed.len(), MIN_SEED_LEN);
+                assert_eq!(
+                    init_msg.owner,
+                    Some(Addr::unchecked(String::from(
+                        "secret1297ctp7t8d2dfm0zdxp4umjjs746s4a5zk34ss"
+                    )))
+                );
+            }
+            msg => panic!("Unexpected message {:?}", msg),
+        }
+
+        // reply records the new strongbox for its owner
+        let instantiate_reply = Reply {
+            id: INSTANTIATE_STRONGBOX_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![Event::new("instantiate")
+                    .add_attribute("contract_address", "strongbox_contract")],
+                data: None,
+            }),
+        };
+        reply(deps.as_mut(), mock_env(), instantiate_reply).unwrap();
+
+        let query_msg = QueryMsg::WithPermit {
+            permit: user_permit,
+            query: QueryWithPermit::MyStrongboxes {},
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: StrongboxesResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            res.strongboxes,
+            vec![Addr::unchecked(String::from("strongbox_contract"))]
+        );
+
+        // replies without the instantiated address are rejected
+        let incomplete_reply = Reply {
+            id: INSTANTIATE_STRONGBOX_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![Event::new("instantiate")],
+                data: None,
+            }),
+        };
+        let res = reply(deps.as_mut(), mock_env(), incomplete_reply);
+        assert_eq!(res.unwrap_err(), ContractError::StrongboxAddressNotFound);
+
+        // unknown replies are rejected
+        let unknown_reply = Reply {
+            id: 99,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        };
+        let res = reply(deps.as_mut(), mock_env(), unknown_reply);
+        assert_eq!(res.unwrap_err(), ContractError::UnknownReply { id: 99 });
+    }
+
+    #[test]
+    fn instantiate_on_behalf_of_owner() {
+        let mut deps = mock_dependencies();
+        let factory_info = mock_info(
+            "factory",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: Some(Addr::unchecked(String::from("creator"))),
+        };
+        instantiate(deps.as_mut(), mock_env(), factory_info, init_msg).unwrap();
+
+        let state = config_read(&deps.storage).load().unwrap();
+        assert_eq!(state.owner, deps.api.addr_canonicalize("creator").unwrap());
+
+        // factory has no rights over the strongbox
+        let factory_info = mock_info(
+            "factory",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let update_msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: String::from("Test strongbox"),
+            expected_revision: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), factory_info, update_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+    }
+
+    #[test]
+    fn guardian_recovery() {
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        let guardian_addresses = vec![
+            Addr::unchecked(String::from("guardian1")),
+            Addr::unchecked(String::from("guardian2")),
+            Addr::unchecked(String::from("guardian3")),
+        ];
+
+        // threshold can't exceed the number of guardians
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let guardians_msg = ExecuteMsg::SetGuardians {
+            guardians: guardian_addresses.clone(),
+            threshold: 4,
+            delay: MIN_RECOVERY_DELAY,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, guardians_msg);
+        assert_eq!(res.unwrap_err(), ContractError::InvalidGuardians);
+
+        // delay must give the owner time to react
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let guardians_msg = ExecuteMsg::SetGuardians {
+            guardians: guardian_addresses.clone(),
+            threshold: 2,
+            delay: 60,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, guardians_msg);
+        assert_eq!(res.unwrap_err(), ContractError::InvalidRecoveryDelay);
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let guardians_msg = ExecuteMsg::SetGuardians {
+            guardians: guardian_addresses.clone(),
+            threshold: 2,
+            delay: u64::MAX,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, guardians_msg);
+        assert_eq!(res.unwrap_err(), ContractError::InvalidRecoveryDelay);
+
+        // only the owner sets guardians
+        let guardian_info = mock_info("guardian1", &[]);
+        let guardians_msg = ExecuteMsg::SetGuardians {
+            guardians: guardian_addresses.clone(),
+            threshold: 2,
+            delay: MIN_RECOVERY_DELAY,
+        };
+        let res = execute(deps.as_mut(), mock_env(), guardian_info, guardians_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let guardians_msg = ExecuteMsg::SetGuardians {
+            guardians: guardian_addresses.clone(),
+            threshold: 2,
+            delay: MIN_RECOVERY_DELAY,
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, guardians_msg).unwrap();
+
+        // only guardians initiate a recovery
+        let other_info = mock_info("anyone", &[]);
+        let initiate_msg = ExecuteMsg::InitiateRecovery {
+            new_owner: Addr::unchecked(String::from("newowner")),
+        };
+        let res = execute(deps.as_mut(), mock_env(), other_info, initiate_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        let guardian_info = mock_info("guardian1", &[]);
+        let initiate_msg = ExecuteMsg::InitiateRecovery {
+            new_owner: Addr::unchecked(String::from("newowner")),
+        };
+        execute(deps.as_mut(), mock_env(), guardian_info, initiate_msg).unwrap();
+
+        let guardian_info = mock_info("guardian2", &[]);
+        let initiate_msg = ExecuteMsg::InitiateRecovery {
+            new_owner: Addr::unchecked(String::from("guardian2")),
+        };
+        let res = execute(deps.as_mut(), mock_env(), guardian_info, initiate_msg);
+        assert_eq!(res.unwrap_err(), ContractError::RecoveryInProgress);
+
+        // the owner can still cancel it
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info,
+            ExecuteMsg::CancelRecovery {},
+        )
+        .unwrap();
+
+        let guardian_info = mock_info("guardian2", &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            guardian_info,
+            ExecuteMsg::ApproveRecovery {},
+        );
+        assert_eq!(res.unwrap_err(), ContractError::RecoveryNotFound);
+
+        let guardian_info = mock_info("guardian1", &[]);
+        let initiate_msg = ExecuteMsg::InitiateRecovery {
+            new_owner: Addr::unchecked(String::from("newowner")),
+        };
+        execute(deps.as_mut(), mock_env(), guardian_info, initiate_msg).unwrap();
+
+        // a single approval isn't enough
+        let other_info = mock_info("anyone", &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            other_info,
+            ExecuteMsg::ExecuteRecovery {},
+        );
+        assert_eq!(res.unwrap_err(), ContractError::RecoveryNotReady);
+
+        let guardian_info = mock_info("guardian2", &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            guardian_info,
+            ExecuteMsg::ApproveRecovery {},
+        )
+        .unwrap();
+
+        // the recovery is visible to the owner
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("creator"))),
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg).unwrap();
+        let vk = created_key(&res);
+
+        let query_msg = QueryMsg::GetRecovery {
+            behalf: Addr::unchecked(String::from("creator")),
+            key: vk.to_string(),
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let value: RecoveryResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            value.recovery,
+            Some(RecoveryInfo {
+                new_owner: Addr::unchecked(String::from("newowner")),
+                approvals: vec![
+                    Addr::unchecked(String::from("guardian1")),
+                    Addr::unchecked(String::from("guardian2")),
+                ],
+                executable_at: mock_env().block.time.seconds() + MIN_RECOVERY_DELAY,
+            })
+        );
+
+        let query_msg = QueryMsg::GetGuardians {
+            behalf: Addr::unchecked(String::from("creator")),
+            key: vk.to_string(),
+            vault: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let value: GuardiansResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            value.guardians,
+            Some(GuardiansInfo {
+                addresses: guardian_addresses.clone(),
+                threshold: 2,
+                delay: MIN_RECOVERY_DELAY,
+            })
+        );
+
+        // enough approvals, but the delay hasn't passed
+        let other_info = mock_info("anyone", &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            other_info,
+            ExecuteMsg::ExecuteRecovery {},
+        );
+        assert_eq!(res.unwrap_err(), ContractError::RecoveryNotReady);
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(MIN_RECOVERY_DELAY);
+        let other_info = mock_info("anyone", &[]);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            other_info,
+            ExecuteMsg::ExecuteRecovery {},
+        )
+        .unwrap();
+
+        // ownership moved to the new owner
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let update_msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: String::from("old owner"),
+            expected_revision: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), owner_info, update_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        let new_owner_info = mock_info("newowner", &[]);
+        let update_msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: String::from("recovered"),
+            expected_revision: None,
+        };
+        execute(deps.as_mut(), env.clone(), new_owner_info, update_msg).unwrap();
+
+        // guardians don't carry over to the new owner
+        let guardian_info = mock_info("guardian1", &[]);
+        let initiate_msg = ExecuteMsg::InitiateRecovery {
+            new_owner: Addr::unchecked(String::from("guardian1")),
+        };
+        let res = execute(deps.as_mut(), env.clone(), guardian_info, initiate_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        let new_owner_info = mock_info("newowner", &[]);
+        let guardians_msg = ExecuteMsg::SetGuardians {
+            guardians: guardian_addresses,
+            threshold: 2,
+            delay: MIN_RECOVERY_DELAY,
+        };
+        execute(deps.as_mut(), env.clone(), new_owner_info, guardians_msg).unwrap();
+
+        // a recovery doesn't survive a transfer of ownership
+        let guardian_info = mock_info("guardian1", &[]);
+        let initiate_msg = ExecuteMsg::InitiateRecovery {
+            new_owner: Addr::unchecked(String::from("guardian1")),
+        };
+        execute(deps.as_mut(), env.clone(), guardian_info, initiate_msg).unwrap();
+
+        let guardian_info = mock_info("guardian2", &[]);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            guardian_info,
+            ExecuteMsg::ApproveRecovery {},
+        )
+        .unwrap();
+
+        let new_owner_info = mock_info("newowner", &[]);
+        let propose_msg = ExecuteMsg::ProposeOwnership {
+            new_owner: Addr::unchecked(String::from("thirdowner")),
+        };
+        execute(deps.as_mut(), env.clone(), new_owner_info, propose_msg).unwrap();
+
+        let third_owner_info = mock_info("thirdowner", &[]);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            third_owner_info,
+            ExecuteMsg::AcceptOwnership {},
+        )
+        .unwrap();
+
+        let mut env = env;
+        env.block.time = env.block.time.plus_seconds(MIN_RECOVERY_DELAY);
+        let other_info = mock_info("anyone", &[]);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            other_info,
+            ExecuteMsg::ExecuteRecovery {},
+        );
+        assert_eq!(res.unwrap_err(), ContractError::RecoveryNotFound);
+
+        // neither do the guardians
+        let query_msg = QueryMsg::GetGuardians {
+            behalf: Addr::unchecked(String::from("creator")),
+            key: vk.to_string(),
+            vault: None,
+        };
+        let res = query(deps.as_ref(), env, query_msg).unwrap();
+        let value: GuardiansResponse = from_binary(&res).unwrap();
+        assert_eq!(value.guardians, None);
+    }
+
+    #[test]
+    fn inheritance() {
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        // inactivity period must be at least a day
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let beneficiary_msg = ExecuteMsg::SetBeneficiary {
+            address: Addr::unchecked(String::from("heir")),
+            inactivity_period: 3600,
+            inheritance: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, beneficiary_msg);
+        assert_eq!(res.unwrap_err(), ContractError::InvalidInactivityPeriod);
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let beneficiary_msg = ExecuteMsg::SetBeneficiary {
+            address: Addr::unchecked(String::from("heir")),
+            inactivity_period: MIN_INACTIVITY_PERIOD,
+            inheritance: Some(Inheritance::Viewer),
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, beneficiary_msg).unwrap();
+
+        // only the beneficiary claims, and only once the owner went silent
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(MIN_INACTIVITY_PERIOD);
+        let other_info = mock_info("anyone", &[]);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            other_info,
+            ExecuteMsg::ClaimInheritance {},
+        );
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        let heir_info = mock_info("heir", &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            heir_info,
+            ExecuteMsg::ClaimInheritance {},
+        );
+        assert_eq!(res.unwrap_err(), ContractError::InheritanceNotReady);
+
+        // a heartbeat resets the timer
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let mut heartbeat_env = mock_env();
+        heartbeat_env.block.time = heartbeat_env.block.time.plus_seconds(3600);
+        execute(
+            deps.as_mut(),
+            heartbeat_env,
+            owner_info,
+            ExecuteMsg::Heartbeat {},
+        )
+        .unwrap();
+
+        let heir_info = mock_info("heir", &[]);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            heir_info,
+            ExecuteMsg::ClaimInheritance {},
+        );
+        assert_eq!(res.unwrap_err(), ContractError::InheritanceNotReady);
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(MIN_INACTIVITY_PERIOD + 3600);
+        let heir_info = mock_info("heir", &[]);
+        let res = execute(
+            deps.as_mut(),
+            env,
+            heir_info,
+            ExecuteMsg::ClaimInheritance {},
+        )
+        .unwrap();
+        let vk = created_key(&res);
+
+        // the beneficiary reads the strongbox but doesn't own it
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("heir")),
+            key: vk.to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        query(deps.as_ref(), mock_env(), query_msg).unwrap();
+
+        let heir_info = mock_info("heir", &[]);
+        let update_msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: String::from("inherited"),
+            expected_revision: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), heir_info, update_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        // the claim can't be repeated
+        let heir_info = mock_info("heir", &[]);
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(2 * MIN_INACTIVITY_PERIOD);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            heir_info,
+            ExecuteMsg::ClaimInheritance {},
+        );
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        // ownership inheritance hands over the strongbox
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let beneficiary_msg = ExecuteMsg::SetBeneficiary {
+            address: Addr::unchecked(String::from("heir")),
+            inactivity_period: MIN_INACTIVITY_PERIOD,
+            inheritance: None,
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, beneficiary_msg).unwrap();
+
+        let heir_info = mock_info("heir", &[]);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            heir_info,
+            ExecuteMsg::ClaimInheritance {},
+        )
+        .unwrap();
+
+        let heir_info = mock_info("heir", &[]);
+        let update_msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: String::from("inherited"),
+            expected_revision: None,
+        };
+        execute(deps.as_mut(), env, heir_info, update_msg).unwrap();
+    }
+
+    #[test]
+    fn owner_change_drops_beneficiary() {
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let beneficiary_msg = ExecuteMsg::SetBeneficiary {
+            address: Addr::unchecked(String::from("heir")),
+            inactivity_period: MIN_INACTIVITY_PERIOD,
+            inheritance: None,
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, beneficiary_msg).unwrap();
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let propose_msg = ExecuteMsg::ProposeOwnership {
+            new_owner: Addr::unchecked(String::from("newowner")),
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, propose_msg).unwrap();
+
+        let mut accept_env = mock_env();
+        accept_env.block.time = accept_env
+            .block
+            .time
+            .plus_seconds(MIN_INACTIVITY_PERIOD - 60);
+        let new_owner_info = mock_info("newowner", &[]);
+        execute(
+            deps.as_mut(),
+            accept_env,
+            new_owner_info,
+            ExecuteMsg::AcceptOwnership {},
+        )
+        .unwrap();
+
+        // the previous owner's beneficiary can't claim the new owner's strongbox
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(MIN_INACTIVITY_PERIOD + 3600);
+        let heir_info = mock_info("heir", &[]);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            heir_info,
+            ExecuteMsg::ClaimInheritance {},
+        );
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        // a huge inactivity period never elapses
+        let new_owner_info = mock_info("newowner", &[]);
+        let beneficiary_msg = ExecuteMsg::SetBeneficiary {
+            address: Addr::unchecked(String::from("heir")),
+            inactivity_period: u64::MAX,
+            inheritance: None,
+        };
+        execute(deps.as_mut(), env.clone(), new_owner_info, beneficiary_msg).unwrap();
+
+        env.block.time = env.block.time.plus_seconds(100 * 365 * 24 * 60 * 60);
+        let heir_info = mock_info("heir", &[]);
+        let res = execute(
+            deps.as_mut(),
+            env,
+            heir_info,
+            ExecuteMsg::ClaimInheritance {},
+        );
+        assert_eq!(res.unwrap_err(), ContractError::InheritanceNotReady);
+    }
+
+    #[test]
+    fn multisig_administration() {
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        let admins = vec![
+            Addr::unchecked(String::from("admin1")),
+            Addr::unchecked(String::from("admin2")),
+            Addr::unchecked(String::from("admin3")),
+        ];
+
+        // threshold can't exceed the number of admins
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let multisig_msg = ExecuteMsg::SetMultisig {
+            admins: admins.clone(),
+            threshold: 4,
+            proposal_lifetime: 3600,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, multisig_msg);
+        assert_eq!(res.unwrap_err(), ContractError::InvalidMultisig);
+
+        // proposals can't outlive the lifetime bound
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let multisig_msg = ExecuteMsg::SetMultisig {
+            admins: admins.clone(),
+            threshold: 2,
+            proposal_lifetime: u64::MAX,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, multisig_msg);
+        assert_eq!(res.unwrap_err(), ContractError::InvalidMultisig);
+
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let multisig_msg = ExecuteMsg::SetMultisig {
+            admins,
+            threshold: 2,
+            proposal_lifetime: 3600,
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, multisig_msg).unwrap();
+
+        // the former owner lost their rights
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let update_msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: String::from("alone"),
+            expected_revision: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, update_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        // only admins propose
+        let other_info = mock_info("anyone", &[]);
+        let propose_msg = ExecuteMsg::Propose {
+            action: Box::new(ExecuteMsg::UpdateStrongbox {
+                strongbox: String::from("outsider"),
+                expected_revision: None,
+            }),
+        };
+        let res = execute(deps.as_mut(), mock_env(), other_info, propose_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        let admin_info = mock_info("admin1", &[]);
+        let propose_msg = ExecuteMsg::Propose {
+            action: Box::new(ExecuteMsg::Approve { proposal_id: 0 }),
+        };
+        let res = execute(deps.as_mut(), mock_env(), admin_info, propose_msg);
+        assert_eq!(res.unwrap_err(), ContractError::InvalidProposal);
+
+        let admin_info = mock_info("admin1", &[]);
+        let propose_msg = ExecuteMsg::Propose {
+            action: Box::new(ExecuteMsg::CreateViewingKey {
+                viewer: Some(Addr::unchecked(String::from("admin1"))),
+                entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+                entropy_bytes: None,
+                scope: None,
+                expires_at_time: None,
+                expires_at_height: None,
+                label: None,
+                padding: None,
+            }),
+        };
+        let res = execute(deps.as_mut(), mock_env(), admin_info, propose_msg).unwrap();
+        assert_eq!(res.attributes[0].value, "0");
+        assert_eq!(res.data, None);
+
+        // approving twice doesn't count
+        let admin_info = mock_info("admin1", &[]);
+        let approve_msg = ExecuteMsg::Approve { proposal_id: 0 };
+        let res = execute(deps.as_mut(), mock_env(), admin_info, approve_msg).unwrap();
+        assert_eq!(res.data, None);
+
+        // the second approval executes the action
+        let admin_info = mock_info("admin2", &[]);
+        let approve_msg = ExecuteMsg::Approve { proposal_id: 0 };
+        let res = execute(deps.as_mut(), mock_env(), admin_info, approve_msg).unwrap();
+        let vk = created_key(&res);
+
+        let admin_info = mock_info("admin3", &[]);
+        let approve_msg = ExecuteMsg::Approve { proposal_id: 0 };
+        let res = execute(deps.as_mut(), mock_env(), admin_info, approve_msg);
+        assert_eq!(res.unwrap_err(), ContractError::ProposalNotFound);
+
+        let admin_info = mock_info("admin1", &[]);
+        let propose_msg = ExecuteMsg::Propose {
+            action: Box::new(ExecuteMsg::UpdateStrongbox {
+                strongbox: String::from("agreed"),
+                expected_revision: None,
+            }),
+        };
+        execute(deps.as_mut(), mock_env(), admin_info, propose_msg).unwrap();
+
+        let admin_info = mock_info("admin3", &[]);
+        let approve_msg = ExecuteMsg::Approve { proposal_id: 1 };
+        execute(deps.as_mut(), mock_env(), admin_info, approve_msg).unwrap();
+
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("admin1")),
+            key: vk.to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let value: StrongboxResponse = from_binary(&res).unwrap();
+        assert_eq!(value.strongbox, "agreed");
+
+        // proposals expire
+        let admin_info = mock_info("admin1", &[]);
+        let propose_msg = ExecuteMsg::Propose {
+            action: Box::new(ExecuteMsg::UpdateStrongbox {
+                strongbox: String::from("too late"),
+                expected_revision: None,
+            }),
+        };
+        execute(deps.as_mut(), mock_env(), admin_info, propose_msg).unwrap();
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(3600);
+        let admin_info = mock_info("admin2", &[]);
+        let approve_msg = ExecuteMsg::Approve { proposal_id: 2 };
+        let res = execute(deps.as_mut(), env, admin_info, approve_msg);
+        assert_eq!(res.unwrap_err(), ContractError::ProposalExpired);
+
+        // proposals made to former admins can't be approved by the new ones
+        let admin_info = mock_info("admin1", &[]);
+        let propose_msg = ExecuteMsg::Propose {
+            action: Box::new(ExecuteMsg::UpdateStrongbox {
+                strongbox: String::from("stale"),
+                expected_revision: None,
+            }),
+        };
+        execute(deps.as_mut(), mock_env(), admin_info, propose_msg).unwrap();
+
+        let admin_info = mock_info("admin2", &[]);
+        let propose_msg = ExecuteMsg::Propose {
+            action: Box::new(ExecuteMsg::SetMultisig {
+                admins: vec![
+                    Addr::unchecked(String::from("admin1")),
+                    Addr::unchecked(String::from("admin4")),
+                ],
+                threshold: 2,
+                proposal_lifetime: 3600,
+            }),
+        };
+        execute(deps.as_mut(), mock_env(), admin_info, propose_msg).unwrap();
+
+        let admin_info = mock_info("admin3", &[]);
+        let approve_msg = ExecuteMsg::Approve { proposal_id: 4 };
+        execute(deps.as_mut(), mock_env(), admin_info, approve_msg).unwrap();
+
+        let admin_info = mock_info("admin4", &[]);
+        let approve_msg = ExecuteMsg::Approve { proposal_id: 3 };
+        let res = execute(deps.as_mut(), mock_env(), admin_info, approve_msg);
+        assert_eq!(res.unwrap_err(), ContractError::ProposalNotFound);
+
+        let admin_info = mock_info("admin2", &[]);
+        let propose_msg = ExecuteMsg::Propose {
+            action: Box::new(ExecuteMsg::UpdateStrongbox {
+                strongbox: String::from("former admin"),
+                expected_revision: None,
+            }),
+        };
+        let res = execute(deps.as_mut(), mock_env(), admin_info, propose_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("admin1")),
+            key: vk.to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let value: StrongboxResponse = from_binary(&res).unwrap();
+        assert_eq!(value.strongbox, "agreed");
+    }
+
+    #[test]
+    fn roles() {
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        for (address, role) in [("backend", Role::Writer), ("keys", Role::KeyManager)] {
+            let owner_info = mock_info(
+                "creator",
+                &[Coin {
+                    denom: "earth".to_string(),
+                    amount: Uint128::new(1000),
+                }],
+            );
+            let grant_msg = ExecuteMsg::GrantRole {
+                address: Addr::unchecked(String::from(address)),
+                role,
+            };
+            execute(deps.as_mut(), mock_env(), owner_info, grant_msg).unwrap();
+        }
+
+        // the owner lists who holds a role
+        let owner_info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: None,
+            entropy: "Hq7sY3nB5mK0pW2eR9tA".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg).unwrap();
+        let owner_vk = created_key(&res);
+
+        let query_msg = QueryMsg::ListRoles {
+            behalf: Addr::unchecked(String::from("creator")),
+            key: owner_vk.to_string(),
+            vault: None,
+            page: None,
+            page_size: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let res: RolesResponse = from_binary(&res).unwrap();
+        assert_eq!(res.total, 2);
+        assert_eq!(
+            res.roles,
+            vec![
+                RoleInfo {
+                    address: Addr::unchecked(String::from("backend")),
+                    roles: vec![Role::Writer],
+                },
+                RoleInfo {
+                    address: Addr::unchecked(String::from("keys")),
+                    roles: vec![Role::KeyManager],
+                },
+            ]
+        );
+
+        // writers update the strongbox but don't mint keys or transfer ownership
+        let writer_info = mock_info("backend", &[]);
+        let update_msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: String::from("from backend"),
+            expected_revision: None,
+        };
+        execute(deps.as_mut(), mock_env(), writer_info, update_msg).unwrap();
+
+        let writer_info = mock_info("backend", &[]);
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("backend"))),
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), writer_info, create_vk_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        let writer_info = mock_info("backend", &[]);
+        let propose_msg = ExecuteMsg::ProposeOwnership {
+            new_owner: Addr::unchecked(String::from("backend")),
+        };
+        let res = execute(deps.as_mut(), mock_env(), writer_info, propose_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        let writer_info = mock_info("backend", &[]);
+        let grant_msg = ExecuteMsg::GrantRole {
+            address: Addr::unchecked(String::from("backend")),
+            role: Role::Admin,
+        };
+        let res = execute(deps.as_mut(), mock_env(), writer_info, grant_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        // key managers mint and revoke keys but don't write
+        let manager_info = mock_info("keys", &[]);
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("user1"))),
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), manager_info, create_vk_msg).unwrap();
+        let vk = created_key(&res);
 
-    return Ok(StrongboxResponse {
-        strongbox: _strongbox,
-    });
-}
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: vk.to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let value: StrongboxResponse = from_binary(&res).unwrap();
+        assert_eq!(value.strongbox, "from backend");
 
-#[cfg(test)]
-mod tests {
+        let manager_info = mock_info("keys", &[]);
+        let update_msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: String::from("from key manager"),
+            expected_revision: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), manager_info, update_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
 
-    use super::*;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{from_binary, Coin, StdError, Uint128};
+        let manager_info = mock_info("keys", &[]);
+        let revoke_msg = ExecuteMsg::RevokeViewingKey {
+            viewer: Addr::unchecked(String::from("user1")),
+        };
+        execute(deps.as_mut(), mock_env(), manager_info, revoke_msg).unwrap();
 
-    #[test]
-    fn proper_initialization() {
-        let mut deps = mock_dependencies();
-        let info = mock_info(
+        // admins hold every role and manage roles
+        let owner_info = mock_info(
             "creator",
             &[Coin {
                 denom: "earth".to_string(),
                 amount: Uint128::new(1000),
             }],
         );
-        let msg = InstantiateMsg {
-            serenity_seed: String::from("init strongbox"),
+        let grant_msg = ExecuteMsg::GrantRole {
+            address: Addr::unchecked(String::from("admin")),
+            role: Role::Admin,
         };
+        execute(deps.as_mut(), mock_env(), owner_info, grant_msg).unwrap();
 
-        // init action will be failed due to seed length
-        let res = instantiate(deps.as_mut(), mock_env(), info, msg);
-        let error_msg = match res {
-            Err(StdError::GenericErr { msg }) => msg,
-            _ => panic!("You need to provide valid seed"),
+        let admin_info = mock_info("admin", &[]);
+        let revoke_msg = ExecuteMsg::RevokeRole {
+            address: Addr::unchecked(String::from("backend")),
+            role: Role::Writer,
         };
-        assert_eq!(error_msg, "You need to provide valid seed");
+        execute(deps.as_mut(), mock_env(), admin_info, revoke_msg).unwrap();
 
-        let info = mock_info(
-            "creator",
-            &[Coin {
-                denom: "earth".to_string(),
-                amount: Uint128::new(1000),
-            }],
-        );
-        let msg = InstantiateMsg {
-            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+        let admin_info = mock_info("admin", &[]);
+        let revoke_msg = ExecuteMsg::RevokeRole {
+            address: Addr::unchecked(String::from("backend")),
+            role: Role::Writer,
         };
-        // we can just call .unwrap() to assert this was a success
-        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
-        assert_eq!(0, res.messages.len());
+        let res = execute(deps.as_mut(), mock_env(), admin_info, revoke_msg);
+        assert_eq!(res.unwrap_err(), ContractError::RoleNotFound);
+
+        let writer_info = mock_info("backend", &[]);
+        let update_msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: String::from("revoked"),
+            expected_revision: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), writer_info, update_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        let admin_info = mock_info("admin", &[]);
+        let update_msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: String::from("from admin"),
+            expected_revision: None,
+        };
+        execute(deps.as_mut(), mock_env(), admin_info, update_msg).unwrap();
+
+        // only the owner proposes an ownership transfer
+        let admin_info = mock_info("admin", &[]);
+        let propose_msg = ExecuteMsg::ProposeOwnership {
+            new_owner: Addr::unchecked(String::from("newowner")),
+        };
+        let res = execute(deps.as_mut(), mock_env(), admin_info, propose_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
     }
 
     #[test]
-    fn update_strongbox() {
+    fn roles_under_multisig() {
         let mut deps = mock_dependencies();
-        let info = mock_info(
+        let owner_info = mock_info(
             "creator",
             &[Coin {
                 denom: "earth".to_string(),
@@ -263,28 +5874,26 @@ mod tests {
         );
         let init_msg = InstantiateMsg {
             serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
         };
-        instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
 
-        // not anyone can update
-        let anyone_info = mock_info(
-            "visitor1",
+        let owner_info = mock_info(
+            "creator",
             &[Coin {
                 denom: "earth".to_string(),
                 amount: Uint128::new(1000),
             }],
         );
-        let update_msg = ExecuteMsg::UpdateStrongbox {
-            strongbox: String::from("Test strongbox"),
-        };
-        let res = execute(deps.as_mut(), mock_env(), anyone_info, update_msg);
-        let error_msg = match res {
-            Err(StdError::GenericErr { msg }) => msg,
-            _ => panic!("You are not allowed"),
+        let grant_msg = ExecuteMsg::GrantRole {
+            address: Addr::unchecked(String::from("admin")),
+            role: Role::Admin,
         };
-        assert_eq!(error_msg, "You are not allowed");
+        execute(deps.as_mut(), mock_env(), owner_info, grant_msg).unwrap();
 
-        // owner can update
         let owner_info = mock_info(
             "creator",
             &[Coin {
@@ -292,15 +5901,67 @@ mod tests {
                 amount: Uint128::new(1000),
             }],
         );
-        let msg = ExecuteMsg::UpdateStrongbox {
-            strongbox: String::from("Test strongbox"),
+        let multisig_msg = ExecuteMsg::SetMultisig {
+            admins: vec![
+                Addr::unchecked(String::from("admin1")),
+                Addr::unchecked(String::from("admin2")),
+            ],
+            threshold: 2,
+            proposal_lifetime: 3600,
         };
-        let res = execute(deps.as_mut(), mock_env(), owner_info, msg).unwrap();
-        assert_eq!(0, res.messages.len());
+        execute(deps.as_mut(), mock_env(), owner_info, multisig_msg).unwrap();
+
+        // a former admin can't take over the strongbox past the multisig
+        let admin_info = mock_info("admin", &[]);
+        let propose_msg = ExecuteMsg::ProposeOwnership {
+            new_owner: Addr::unchecked(String::from("admin")),
+        };
+        let res = execute(deps.as_mut(), mock_env(), admin_info, propose_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        let admin_info = mock_info("admin", &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            admin_info,
+            ExecuteMsg::AcceptOwnership {},
+        );
+        assert_eq!(res.unwrap_err(), ContractError::OwnershipProposalNotFound);
+
+        // nor act alone with the roles it was granted
+        let admin_info = mock_info("admin", &[]);
+        let update_msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: String::from("from admin"),
+            expected_revision: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), admin_info, update_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        let admin_info = mock_info("admin", &[]);
+        let grant_msg = ExecuteMsg::GrantRole {
+            address: Addr::unchecked(String::from("backend")),
+            role: Role::Writer,
+        };
+        let res = execute(deps.as_mut(), mock_env(), admin_info, grant_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
+
+        let admin_info = mock_info("admin", &[]);
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("admin"))),
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), admin_info, create_vk_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
     }
 
     #[test]
-    fn create_viewing_key() {
+    fn owner_change_drops_roles() {
         let mut deps = mock_dependencies();
         let owner_info = mock_info(
             "creator",
@@ -311,51 +5972,142 @@ mod tests {
         );
         let init_msg = InstantiateMsg {
             serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
         };
         instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
 
-        // entropy length should be fixed
-        let anyone_info = mock_info(
-            "visitor1",
+        let owner_info = mock_info(
+            "creator",
             &[Coin {
                 denom: "earth".to_string(),
                 amount: Uint128::new(1000),
             }],
         );
+        let guardians_msg = ExecuteMsg::SetGuardians {
+            guardians: vec![Addr::unchecked(String::from("guardian1"))],
+            threshold: 1,
+            delay: MIN_RECOVERY_DELAY,
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, guardians_msg).unwrap();
+
+        // someone holding the stolen owner key makes themselves admin
+        let thief_info = mock_info("creator", &[]);
+        let grant_msg = ExecuteMsg::GrantRole {
+            address: Addr::unchecked(String::from("thief")),
+            role: Role::Admin,
+        };
+        execute(deps.as_mut(), mock_env(), thief_info, grant_msg).unwrap();
+
+        // the guardians recover the strongbox
+        let guardian_info = mock_info("guardian1", &[]);
+        let initiate_msg = ExecuteMsg::InitiateRecovery {
+            new_owner: Addr::unchecked(String::from("newowner")),
+        };
+        execute(deps.as_mut(), mock_env(), guardian_info, initiate_msg).unwrap();
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(MIN_RECOVERY_DELAY);
+        let other_info = mock_info("anyone", &[]);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            other_info,
+            ExecuteMsg::ExecuteRecovery {},
+        )
+        .unwrap();
+
+        // the roles granted under the previous owner are gone
+        let thief_info = mock_info("thief", &[]);
         let create_vk_msg = ExecuteMsg::CreateViewingKey {
-            viewer: Addr::unchecked(String::from("user1")),
-            entropy: "supbro".to_string(),
+            viewer: Some(Addr::unchecked(String::from("thief"))),
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
             padding: None,
         };
+        let res = execute(deps.as_mut(), env.clone(), thief_info, create_vk_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
 
-        let res = execute(deps.as_mut(), mock_env(), anyone_info, create_vk_msg);
-        let error_msg = match res {
-            Err(StdError::GenericErr { msg }) => msg,
-            _ => panic!("You need to provide valid entropy"),
+        let thief_info = mock_info("thief", &[]);
+        let update_msg = ExecuteMsg::UpdateStrongbox {
+            strongbox: String::from("from thief"),
+            expected_revision: None,
         };
-        assert_eq!(error_msg, "You need to provide valid entropy");
+        let res = execute(deps.as_mut(), env.clone(), thief_info, update_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
 
-        // only owner can create viewing key
-        let anyone_info = mock_info(
-            "visitor2",
+        let new_owner_info = mock_info("newowner", &[]);
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: None,
+            entropy: "9rT2kLw8xQp4vN6bZc1M".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), new_owner_info, create_vk_msg).unwrap();
+        let vk = created_key(&res);
+
+        let query_msg = QueryMsg::ListRoles {
+            behalf: Addr::unchecked(String::from("newowner")),
+            key: vk.to_string(),
+            vault: None,
+            page: None,
+            page_size: None,
+        };
+        let res = query(deps.as_ref(), env, query_msg).unwrap();
+        let res: RolesResponse = from_binary(&res).unwrap();
+        assert_eq!(res.total, 0);
+        assert_eq!(res.roles, vec![]);
+    }
+
+    #[test]
+    fn rotate_seed() {
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "creator",
             &[Coin {
                 denom: "earth".to_string(),
                 amount: Uint128::new(1000),
             }],
         );
-        let create_vk_msg = ExecuteMsg::CreateViewingKey {
-            viewer: Addr::unchecked(String::from("user1")),
-            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
-            padding: None,
+        let init_msg = InstantiateMsg {
+            serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
         };
-        let res = execute(deps.as_mut(), mock_env(), anyone_info, create_vk_msg);
-        let error_msg = match res {
-            Err(StdError::GenericErr { msg }) => msg,
-            _ => panic!("You are not allowed"),
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        let viewer = deps.api.addr_canonicalize("user1").unwrap();
+        let entropy = b"2418D8fZhQs8jIzuhiZ8";
+        let seed = config_read(&deps.storage).load().unwrap().serenity_seed;
+
+        // block randomness changes the key
+        let mut env = mock_env();
+        env.block.random = Some(Binary::from(b"another random value".to_vec()));
+        assert_ne!(
+            ViewingKey::new(&mock_env(), &viewer, &seed, entropy),
+            ViewingKey::new(&env, &viewer, &seed, entropy)
+        );
+
+        let other_info = mock_info("anyone", &[]);
+        let rotate_msg = ExecuteMsg::RotateSeed {
+            entropy: "9rT2kLw8xQp4vN6bZc1M".to_string(),
+            entropy_bytes: None,
         };
-        assert_eq!(error_msg, "You are not allowed");
+        let res = execute(deps.as_mut(), mock_env(), other_info, rotate_msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized);
 
-        // owner can create viewing key
         let owner_info = mock_info(
             "creator",
             &[Coin {
@@ -363,20 +6115,40 @@ mod tests {
                 amount: Uint128::new(1000),
             }],
         );
-        let create_vk_msg = ExecuteMsg::CreateViewingKey {
-            viewer: Addr::unchecked(String::from("user2")),
-            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
-            padding: None,
-        };
-        let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg).unwrap();
-        let vk: ViewingKey = match from_binary(&res.data.unwrap()).unwrap() {
-            Some(data) => data,
-            _ => panic!("Unexpected result from handle"),
+        let rotate_msg = ExecuteMsg::RotateSeed {
+            entropy: "short".to_string(),
+            entropy_bytes: None,
         };
+        let res = execute(deps.as_mut(), mock_env(), owner_info, rotate_msg);
+        assert_eq!(res.unwrap_err(), ContractError::InvalidEntropyLength);
 
-        assert!(vk.as_bytes().len() > 0, "Viewing key not valid");
+        // every rotation yields a new seed, even with the same entropy and block
+        let mut seeds = vec![seed];
+        for _ in 0..2 {
+            let owner_info = mock_info(
+                "creator",
+                &[Coin {
+                    denom: "earth".to_string(),
+                    amount: Uint128::new(1000),
+                }],
+            );
+            let rotate_msg = ExecuteMsg::RotateSeed {
+                entropy: "9rT2kLw8xQp4vN6bZc1M".to_string(),
+                entropy_bytes: None,
+            };
+            execute(deps.as_mut(), mock_env(), owner_info, rotate_msg).unwrap();
+            seeds.push(config_read(&deps.storage).load().unwrap().serenity_seed);
+        }
 
-        // owner can't use same seed for create viewing key
+        let keys: Vec<ViewingKey> = seeds
+            .iter()
+            .map(|seed| ViewingKey::new(&mock_env(), &viewer, seed, entropy))
+            .collect();
+        assert_ne!(keys[0], keys[1]);
+        assert_ne!(keys[1], keys[2]);
+        assert_ne!(keys[0], keys[2]);
+
+        // keys minted after a rotation come from the new seed
         let owner_info = mock_info(
             "creator",
             &[Coin {
@@ -385,20 +6157,23 @@ mod tests {
             }],
         );
         let create_vk_msg = ExecuteMsg::CreateViewingKey {
-            viewer: Addr::unchecked(String::from("user2")),
+            viewer: Some(Addr::unchecked(String::from("user1"))),
             entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
             padding: None,
         };
-        let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg);
-        let error_msg = match res {
-            Err(StdError::GenericErr { msg }) => msg,
-            _ => panic!("You need to use another entropy"),
-        };
-        assert_eq!(error_msg, "You need to use another entropy");
+        let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg).unwrap();
+        let vk = created_key(&res);
+        let owner = deps.api.addr_canonicalize("creator").unwrap();
+        assert_eq!(vk, ViewingKey::new(&mock_env(), &owner, &seeds[2], entropy));
     }
 
     #[test]
-    fn query_strongbox() {
+    fn wallet_viewing_keys() {
         let mut deps = mock_dependencies();
         let owner_info = mock_info(
             "creator",
@@ -409,9 +6184,14 @@ mod tests {
         );
         let init_msg = InstantiateMsg {
             serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
         };
         instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
 
+        // wallets send the SNIP-20 message shapes
         let owner_info = mock_info(
             "creator",
             &[Coin {
@@ -419,10 +6199,23 @@ mod tests {
                 amount: Uint128::new(1000),
             }],
         );
-        let update_msg = ExecuteMsg::UpdateStrongbox {
-            strongbox: String::from("Test strongbox"),
+        let create_vk_msg: ExecuteMsg =
+            from_slice(br#"{"create_viewing_key":{"entropy":"2418D8fZhQs8jIzuhiZ8"}}"#).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg).unwrap();
+        let vk = created_key(&res);
+        assert!(vk.to_string().starts_with(VIEWING_KEY_PREFIX));
+        assert_eq!(
+            res.data.unwrap(),
+            Binary::from(format!(r#"{{"create_viewing_key":{{"key":"{}"}}}}"#, vk).as_bytes())
+        );
+
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("creator")),
+            key: vk.to_string(),
+            vault: None,
+            encrypt_to: None,
         };
-        execute(deps.as_mut(), mock_env(), owner_info, update_msg).unwrap();
+        query(deps.as_ref(), mock_env(), query_msg).unwrap();
 
         let owner_info = mock_info(
             "creator",
@@ -431,41 +6224,54 @@ mod tests {
                 amount: Uint128::new(1000),
             }],
         );
-        let create_vk_msg = ExecuteMsg::CreateViewingKey {
-            viewer: Addr::unchecked(String::from("user1")),
-            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
-            padding: None,
-        };
-        let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg).unwrap();
-        let vk: ViewingKey = from_binary(&res.data.unwrap()).unwrap();
+        let set_vk_msg: ExecuteMsg =
+            from_slice(br#"{"set_viewing_key":{"key":"wallet key"}}"#).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), owner_info, set_vk_msg).unwrap();
+        assert_eq!(
+            res.data.unwrap(),
+            Binary::from(br#"{"set_viewing_key":{"status":"success"}}"#.as_slice())
+        );
 
-        // other user can't use viewing key
         let query_msg = QueryMsg::GetStrongbox {
-            behalf: Addr::unchecked(String::from("user2")),
-            key: vk.to_string(),
-        };
-        let res = query(deps.as_ref(), mock_env(), query_msg);
-        let error_msg = match res {
-            Err(StdError::GenericErr { msg }) => msg,
-            _ => panic!("Your viewing key does not matched"),
+            behalf: Addr::unchecked(String::from("creator")),
+            key: String::from("wallet key"),
+            vault: None,
+            encrypt_to: None,
         };
-        assert_eq!(error_msg, "Your viewing key does not matched");
+        query(deps.as_ref(), mock_env(), query_msg).unwrap();
+
+        // keys in the former format keep working
+        let viewer = deps.api.addr_canonicalize("user1").unwrap();
+        let legacy_key = ViewingKey(String::from("strongbox_key_bGVnYWN5IGtleQ=="));
+        let contract = deps
+            .api
+            .addr_canonicalize(mock_env().contract.address.as_str())
+            .unwrap();
+        write_viewing_key(
+            &mut deps.storage,
+            &contract,
+            &viewer,
+            &legacy_key,
+            ViewerScope::All,
+            Expiration::default(),
+            0,
+        )
+        .unwrap();
 
-        // correct user can use viewing key for query strongbox
         let query_msg = QueryMsg::GetStrongbox {
             behalf: Addr::unchecked(String::from("user1")),
-            key: vk.to_string(),
+            key: legacy_key.to_string(),
+            vault: None,
+            encrypt_to: None,
         };
-        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
-        let res: StrongboxResponse = from_binary(&res).unwrap();
-        assert_eq!(res.strongbox, "Test strongbox");
+        query(deps.as_ref(), mock_env(), query_msg).unwrap();
     }
 
     #[test]
-    fn transfer_ownership() {
+    fn bound_viewing_key_hash() {
         let mut deps = mock_dependencies();
         let owner_info = mock_info(
-            "creator1",
+            "creator",
             &[Coin {
                 denom: "earth".to_string(),
                 amount: Uint128::new(1000),
@@ -473,54 +6279,201 @@ mod tests {
         );
         let init_msg = InstantiateMsg {
             serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
         };
         instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
 
         let owner_info = mock_info(
-            "creator1",
+            "creator",
             &[Coin {
                 denom: "earth".to_string(),
                 amount: Uint128::new(1000),
             }],
         );
-        let update_msg = ExecuteMsg::TransferOwnership {
-            new_owner: Addr::unchecked("creator2"),
+        let create_vk_msg = ExecuteMsg::CreateViewingKey {
+            viewer: Some(Addr::unchecked(String::from("user1"))),
+            entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
+            padding: None,
         };
-        execute(deps.as_mut(), mock_env(), owner_info, update_msg).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg).unwrap();
+        let vk = created_key(&res);
 
-        // Old owner can't update strongbox
-        let old_owner_info = mock_info(
-            "creator1",
-            &[Coin {
-                denom: "earth".to_string(),
-                amount: Uint128::new(1000),
-            }],
+        // the stored hash is keyed, so it can't be recomputed from the key and addresses alone
+        let user1 = deps.api.addr_canonicalize("user1").unwrap();
+        let user2 = deps.api.addr_canonicalize("user2").unwrap();
+        let contract = deps
+            .api
+            .addr_canonicalize(mock_env().contract.address.as_str())
+            .unwrap();
+        let record = read_viewing_key(&deps.storage, &user1).unwrap();
+        assert_eq!(record.algorithm, KeyHashAlgorithm::HmacSha256);
+        assert_ne!(
+            record.key_hash.as_slice(),
+            vk.hash(KeyHashAlgorithm::HmacSha256, &[], &contract, &user1)
         );
-        let update_msg = ExecuteMsg::UpdateStrongbox {
-            strongbox: String::from("Test strongbox"),
+
+        // the stored hash is only valid in the slot it was written to
+        let mut user_key_store = PrefixedStorage::new(&mut deps.storage, PREFIX_VIEWING_KEY);
+        let record = user_key_store.get(user1.as_slice()).unwrap();
+        user_key_store.set(user2.as_slice(), &record);
+
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user2")),
+            key: vk.to_string(),
+            vault: None,
+            encrypt_to: None,
         };
-        let res = execute(deps.as_mut(), mock_env(), old_owner_info, update_msg);
-        match res {
-            Err(StdError::GenericErr { msg }) => msg,
-            _ => panic!("You are not allowed"),
+        let res = query(deps.as_ref(), mock_env(), query_msg);
+        assert_eq!(res.unwrap_err(), ContractError::InvalidViewingKey);
+
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: vk.to_string(),
+            vault: None,
+            encrypt_to: None,
         };
+        query(deps.as_ref(), mock_env(), query_msg.clone()).unwrap();
 
-        // New owner can update strongbox
-        let new_owner_info = mock_info(
-            "creator2",
+        // and only for this contract
+        let mut env = mock_env();
+        env.contract.address = Addr::unchecked(String::from("othercontract"));
+        let res = query(deps.as_ref(), env, query_msg);
+        assert_eq!(res.unwrap_err(), ContractError::InvalidViewingKey);
+
+        // hashes written before keys were bound still verify
+        let key = ViewingKey(String::from("strongbox_key_legacy"));
+        let legacy_record = ViewerRecord {
+            key_hash: Binary(sha_256(key.as_bytes()).to_vec()),
+            algorithm: KeyHashAlgorithm::Sha256,
+            scope: ViewerScope::All,
+            expiration: Expiration::default(),
+            epoch: 0,
+        };
+        let mut user_key_store = PrefixedStorage::new(&mut deps.storage, PREFIX_VIEWING_KEY);
+        user_key_store.set(user2.as_slice(), &to_vec(&legacy_record).unwrap());
+
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user2")),
+            key: key.to_string(),
+            vault: None,
+            encrypt_to: None,
+        };
+        query(deps.as_ref(), mock_env(), query_msg).unwrap();
+    }
+
+    #[test]
+    fn binary_seed_and_entropy() {
+        // seeds are measured in bytes, within bounds
+        for (seed, seed_bytes, valid) in [
+            (String::new(), Some(Binary(vec![7u8; 64])), true),
+            (
+                String::new(),
+                Some(Binary(vec![7u8; MAX_SEED_LEN + 1])),
+                false,
+            ),
+            (
+                String::new(),
+                Some(Binary(vec![7u8; MIN_SEED_LEN - 1])),
+                false,
+            ),
+            (
+                String::from("a seed longer than thirty-two bytes"),
+                None,
+                true,
+            ),
+        ] {
+            let mut deps = mock_dependencies();
+            let owner_info = mock_info(
+                "creator",
+                &[Coin {
+                    denom: "earth".to_string(),
+                    amount: Uint128::new(1000),
+                }],
+            );
+            let init_msg = InstantiateMsg {
+                serenity_seed: seed,
+                serenity_seed_bytes: seed_bytes,
+                multi_tenant: None,
+                factory: None,
+                owner: None,
+            };
+            let res = instantiate(deps.as_mut(), mock_env(), owner_info, init_msg);
+            if valid {
+                res.unwrap();
+            } else {
+                assert_eq!(res.unwrap_err(), ContractError::InvalidSeedLength);
+            }
+        }
+
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info(
+            "creator",
             &[Coin {
                 denom: "earth".to_string(),
                 amount: Uint128::new(1000),
             }],
         );
-        let update_msg = ExecuteMsg::UpdateStrongbox {
-            strongbox: String::from("Test strongbox"),
-        };
-        execute(deps.as_mut(), mock_env(), new_owner_info, update_msg).unwrap();
+        let init_msg: InstantiateMsg = from_slice(
+            br#"{"serenity_seed_bytes":"c2VyZW5pdHkgc3Ryb25nYm94IGJpbmFyeSBzZWVkIGJ5dGVz"}"#,
+        )
+        .unwrap();
+        instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
+
+        // entropy as well
+        for (entropy, entropy_bytes, valid) in [
+            (String::new(), Some(Binary(vec![1u8; 32])), true),
+            (
+                String::new(),
+                Some(Binary(vec![2u8; MAX_ENTROPY_LEN + 1])),
+                false,
+            ),
+            (
+                String::new(),
+                Some(Binary(vec![3u8; MIN_ENTROPY_LEN - 1])),
+                false,
+            ),
+            (
+                String::from("entropy from a wallet, longer than before"),
+                None,
+                true,
+            ),
+        ] {
+            let owner_info = mock_info(
+                "creator",
+                &[Coin {
+                    denom: "earth".to_string(),
+                    amount: Uint128::new(1000),
+                }],
+            );
+            let create_vk_msg = ExecuteMsg::CreateViewingKey {
+                viewer: Some(Addr::unchecked(String::from("user1"))),
+                entropy,
+                entropy_bytes,
+                scope: None,
+                expires_at_time: None,
+                expires_at_height: None,
+                label: None,
+                padding: None,
+            };
+            let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg);
+            if valid {
+                res.unwrap();
+            } else {
+                assert_eq!(res.unwrap_err(), ContractError::InvalidEntropyLength);
+            }
+        }
     }
 
     #[test]
-    fn revoke_viewing_key() {
+    fn encrypted_strongbox() {
         let mut deps = mock_dependencies();
         let owner_info = mock_info(
             "creator",
@@ -531,6 +6484,10 @@ mod tests {
         );
         let init_msg = InstantiateMsg {
             serenity_seed: String::from("r5ypLSFsvpFYFfbfv05USo7wMlFjvoGh"),
+            serenity_seed_bytes: None,
+            multi_tenant: None,
+            factory: None,
+            owner: None,
         };
         instantiate(deps.as_mut(), mock_env(), owner_info, init_msg).unwrap();
 
@@ -543,6 +6500,7 @@ mod tests {
         );
         let update_msg = ExecuteMsg::UpdateStrongbox {
             strongbox: String::from("Test strongbox"),
+            expected_revision: None,
         };
         execute(deps.as_mut(), mock_env(), owner_info, update_msg).unwrap();
 
@@ -554,36 +6512,80 @@ mod tests {
             }],
         );
         let create_vk_msg = ExecuteMsg::CreateViewingKey {
-            viewer: Addr::unchecked(String::from("user1")),
+            viewer: Some(Addr::unchecked(String::from("user1"))),
             entropy: "2418D8fZhQs8jIzuhiZ8".to_string(),
+            entropy_bytes: None,
+            scope: None,
+            expires_at_time: None,
+            expires_at_height: None,
+            label: None,
             padding: None,
         };
         let res = execute(deps.as_mut(), mock_env(), owner_info, create_vk_msg).unwrap();
-        let vk: ViewingKey = from_binary(&res.data.unwrap()).unwrap();
+        let vk = created_key(&res);
 
-        // owner can revoke viewing key
-        let owner_info = mock_info(
-            "creator",
-            &[Coin {
-                denom: "earth".to_string(),
-                amount: Uint128::new(1000),
-            }],
+        let private_key = [1u8; 32];
+        let public_key = PrivateKey::parse(&private_key)
+            .unwrap()
+            .pubkey()
+            .serialize_compressed();
+
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: vk.to_string(),
+            vault: None,
+            encrypt_to: Some(Binary(public_key.to_vec())),
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let encrypted: EncryptedResponse = from_binary(&res).unwrap();
+        assert!(from_binary::<StrongboxResponse>(&res).is_err());
+
+        // only the holder of the private key reads it
+        let value = client::decrypt_strongbox(&private_key, &encrypted).unwrap();
+        assert_eq!(
+            value,
+            StrongboxResponse {
+                strongbox: String::from("Test strongbox"),
+                revision: 1,
+            }
         );
-        let revoke_msg = ExecuteMsg::RevokeViewingKey {
-            viewer: Addr::unchecked(String::from("user1")),
+        assert_eq!(
+            client::decrypt_strongbox(&[2u8; 32], &encrypted).unwrap_err(),
+            ContractError::DecryptionFailed
+        );
+
+        // tampering is detected
+        let mut tampered = encrypted.clone();
+        tampered.ciphertext.0[0] ^= 1;
+        assert_eq!(
+            client::decrypt_strongbox(&private_key, &tampered).unwrap_err(),
+            ContractError::DecryptionFailed
+        );
+
+        // a later block encrypts with another ephemeral key
+        let mut env = mock_env();
+        env.block.height += 1;
+        let query_msg = QueryMsg::GetStrongbox {
+            behalf: Addr::unchecked(String::from("user1")),
+            key: vk.to_string(),
+            vault: None,
+            encrypt_to: Some(Binary(public_key.to_vec())),
         };
-        execute(deps.as_mut(), mock_env(), owner_info, revoke_msg).unwrap();
+        let res = query(deps.as_ref(), env, query_msg).unwrap();
+        let other: EncryptedResponse = from_binary(&res).unwrap();
+        assert_ne!(other.ephemeral_public_key, encrypted.ephemeral_public_key);
+        assert_eq!(
+            client::decrypt_strongbox(&private_key, &other).unwrap(),
+            value
+        );
 
-        // user can't view strongbox with revoked key
         let query_msg = QueryMsg::GetStrongbox {
             behalf: Addr::unchecked(String::from("user1")),
             key: vk.to_string(),
+            vault: None,
+            encrypt_to: Some(Binary(vec![4u8; 33])),
         };
         let res = query(deps.as_ref(), mock_env(), query_msg);
-        let error_msg = match res {
-            Err(StdError::GenericErr { msg }) => msg,
-            _ => panic!("Your viewing key does not matched"),
-        };
-        assert_eq!(error_msg, "Your viewing key does not matched");
+        assert_eq!(res.unwrap_err(), ContractError::InvalidPublicKey);
     }
 }
@@ -0,0 +1,61 @@
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use cosmwasm_std::Binary;
+use secp256k1::ecdh::SharedSecret;
+use secret_toolkit_crypto::secp256k1::{PrivateKey, PublicKey};
+
+use crate::error::ContractError;
+use crate::msg::EncryptedResponse;
+
+pub const NONCE_SIZE: usize = 12;
+
+/// Symmetric key agreed on by the holders of `private_key` and of the key behind `public_key`,
+/// the SHA-256 hash of the compressed shared point
+fn shared_key(private_key: &PrivateKey, public_key: &PublicKey) -> Result<[u8; 32], ContractError> {
+    let point = secp256k1::PublicKey::from_slice(&public_key.serialize())
+        .map_err(|_| ContractError::InvalidPublicKey)?;
+    let scalar = secp256k1::SecretKey::from_slice(&private_key.serialize())
+        .map_err(|_| ContractError::InvalidPrivateKey)?;
+
+    Ok(SharedSecret::new(&point, &scalar).secret_bytes())
+}
+
+/// Encrypts `plaintext` to `recipient` with ChaCha20-Poly1305, under a key agreed on with
+/// `ephemeral_key`. `nonce` must never be reused with the same ephemeral key.
+pub fn encrypt(
+    recipient: &PublicKey,
+    ephemeral_key: &PrivateKey,
+    nonce: &[u8; NONCE_SIZE],
+    plaintext: &[u8],
+) -> Result<EncryptedResponse, ContractError> {
+    let key = shared_key(ephemeral_key, recipient)?;
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(&key))
+        .encrypt(Nonce::from_slice(nonce), plaintext)
+        .map_err(|_| ContractError::EncryptionFailed)?;
+
+    Ok(EncryptedResponse {
+        ephemeral_public_key: Binary(ephemeral_key.pubkey().serialize_compressed().to_vec()),
+        nonce: Binary(nonce.to_vec()),
+        ciphertext: Binary(ciphertext),
+    })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn decrypt(
+    private_key: &PrivateKey,
+    encrypted: &EncryptedResponse,
+) -> Result<Vec<u8>, ContractError> {
+    if encrypted.nonce.len() != NONCE_SIZE {
+        return Err(ContractError::DecryptionFailed);
+    }
+
+    let ephemeral_public_key = PublicKey::parse(encrypted.ephemeral_public_key.as_slice())
+        .map_err(|_| ContractError::InvalidPublicKey)?;
+    let key = shared_key(private_key, &ephemeral_public_key)?;
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+        .decrypt(
+            Nonce::from_slice(encrypted.nonce.as_slice()),
+            encrypted.ciphertext.as_slice(),
+        )
+        .map_err(|_| ContractError::DecryptionFailed)
+}
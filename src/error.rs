@@ -0,0 +1,140 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("You are not allowed")]
+    Unauthorized,
+
+    #[error("You need to provide a seed of 32 to 256 bytes")]
+    InvalidSeedLength,
+
+    #[error("You need to provide entropy of 20 to 256 bytes")]
+    InvalidEntropyLength,
+
+    #[error("You need to use another entropy")]
+    DuplicateEntropy,
+
+    #[error("You need to provide valid expiration")]
+    InvalidExpiration,
+
+    #[error("Viewing key not exists")]
+    ViewingKeyNotFound,
+
+    #[error("Your viewing key does not matched")]
+    InvalidViewingKey,
+
+    #[error("Your viewing key has expired")]
+    ViewingKeyExpired,
+
+    #[error("Your viewing key is out of scope")]
+    OutOfScope,
+
+    #[error("Strongbox is locked down")]
+    LockedDown,
+
+    #[error("Strongbox is not in multi-tenant mode")]
+    NotMultiTenant,
+
+    #[error("Vault not exists")]
+    VaultNotFound,
+
+    #[error("Vault already exists")]
+    VaultExists,
+
+    #[error("This message can't be sent to a vault")]
+    InvalidVaultMessage,
+
+    #[error("Unknown reply id {id}")]
+    UnknownReply { id: u64 },
+
+    #[error("Strongbox instantiation failed: {error}")]
+    StrongboxInstantiationFailed { error: String },
+
+    #[error("Instantiated strongbox address not found")]
+    StrongboxAddressNotFound,
+
+    #[error("You need to provide valid guardians")]
+    InvalidGuardians,
+
+    #[error("Recovery delay must be between one day and one year")]
+    InvalidRecoveryDelay,
+
+    #[error("Recovery not exists")]
+    RecoveryNotFound,
+
+    #[error("Recovery is already in progress")]
+    RecoveryInProgress,
+
+    #[error("Recovery can't be executed yet")]
+    RecoveryNotReady,
+
+    #[error("Inactivity period is too short")]
+    InvalidInactivityPeriod,
+
+    #[error("Owner was active too recently")]
+    InheritanceNotReady,
+
+    #[error("Invalid multisig admins, threshold or proposal lifetime")]
+    InvalidMultisig,
+
+    #[error("Message can't be proposed")]
+    InvalidProposal,
+
+    #[error("Proposal not exists")]
+    ProposalNotFound,
+
+    #[error("Proposal has expired")]
+    ProposalExpired,
+
+    #[error("Role not exists")]
+    RoleNotFound,
+
+    #[error("Invalid secp256k1 public key")]
+    InvalidPublicKey,
+
+    #[error("Invalid secp256k1 private key")]
+    InvalidPrivateKey,
+
+    #[error("Response encryption failed")]
+    EncryptionFailed,
+
+    #[error("Response decryption failed")]
+    DecryptionFailed,
+
+    #[error("Viewer grant not exists")]
+    GrantNotFound,
+
+    #[error("Your viewer grant has expired")]
+    GrantExpired,
+
+    #[error("You need to provide valid label")]
+    InvalidLabel,
+
+    #[error("Entry not exists")]
+    EntryNotFound,
+
+    #[error("Ownership proposal not exists")]
+    OwnershipProposalNotFound,
+
+    #[error("Strongbox revision mismatch: expected {expected}, found {actual}")]
+    RevisionMismatch { expected: u64, actual: u64 },
+
+    #[error("Revision not exists")]
+    RevisionNotFound,
+
+    #[error("You need to provide valid history depth")]
+    InvalidHistoryDepth,
+
+    #[error("Cannot migrate from {contract}")]
+    InvalidMigration { contract: String },
+
+    #[error("Cannot migrate from version {version}")]
+    InvalidMigrationVersion { version: String },
+
+    #[error("No permission to query strongbox, got permissions {permissions}")]
+    MissingPermission { permissions: String },
+}
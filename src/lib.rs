@@ -1,4 +1,8 @@
+#[cfg(not(target_arch = "wasm32"))]
+pub mod client;
 pub mod contract;
+mod ecies;
+pub mod error;
 pub mod msg;
 pub mod state;
 mod viewing_key;
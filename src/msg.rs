@@ -1,50 +1,500 @@
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Binary};
 use schemars::JsonSchema;
+use secret_toolkit::permit::Permit;
 use serde::{Deserialize, Serialize};
 
+use crate::state::{Inheritance, Role, ViewerScope};
 use crate::viewing_key::ViewingKey;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
+    #[serde(default)]
     pub serenity_seed: String,
+    /// Seed bytes as base64, used instead of `serenity_seed` when set
+    pub serenity_seed_bytes: Option<Binary>,
+    /// Lets any address create its own vault with `CreateStrongbox`
+    pub multi_tenant: Option<bool>,
+    /// Makes `CreateStrongbox` instantiate a new strongbox contract from this code
+    pub factory: Option<StrongboxCode>,
+    /// Owner of the strongbox, the sender if unset
+    pub owner: Option<Addr>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StrongboxCode {
+    pub code_id: u64,
+    pub code_hash: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     UpdateStrongbox {
         strongbox: String,
+        /// Rejects the update unless the current revision matches
+        expected_revision: Option<u64>,
     },
+    /// Mints a key for `viewer` when sent by a key manager, or for the sender otherwise
     CreateViewingKey {
-        viewer: Addr,
+        viewer: Option<Addr>,
+        #[serde(default)]
         entropy: String,
+        /// Entropy bytes as base64, used instead of `entropy` when set
+        entropy_bytes: Option<Binary>,
+        scope: Option<ViewerScope>,
+        /// Block time in seconds after which the key stops working
+        expires_at_time: Option<u64>,
+        /// Block height after which the key stops working
+        expires_at_height: Option<u64>,
+        /// Shown in `ListViewers`; a viewer creating their own key gets the grant's label
+        label: Option<String>,
         padding: Option<String>,
     },
 
-    TransferOwnership {
+    ProposeOwnership {
         new_owner: Addr,
     },
+    AcceptOwnership {},
+    CancelOwnershipProposal {},
     RevokeViewingKey {
         viewer: Addr,
     },
+    SetEntry {
+        label: String,
+        value: String,
+    },
+    RemoveEntry {
+        label: String,
+    },
+    RevokePermit {
+        name: String,
+    },
+    RestoreRevision {
+        revision: u64,
+    },
+    SetHistoryDepth {
+        depth: u32,
+    },
+    GrantViewer {
+        viewer: Addr,
+        scope: Option<ViewerScope>,
+        expires_at_time: Option<u64>,
+        expires_at_height: Option<u64>,
+        label: Option<String>,
+    },
+    RevokeViewerGrant {
+        viewer: Addr,
+    },
+    SetViewingKey {
+        key: String,
+        padding: Option<String>,
+    },
+    /// Derives a new seed for viewing keys from the current one, `entropy` and the
+    /// block's randomness
+    RotateSeed {
+        #[serde(default)]
+        entropy: String,
+        entropy_bytes: Option<Binary>,
+    },
+    /// Invalidates every viewing key issued so far
+    RevokeAllViewingKeys {},
+    /// Rejects every query not made by the owner until lifted or `until` (block time in seconds)
+    Lockdown {
+        until: Option<u64>,
+    },
+    LiftLockdown {},
+    /// Replaces the guardians; an empty list disables recovery
+    SetGuardians {
+        guardians: Vec<Addr>,
+        threshold: u32,
+        /// Seconds the owner has to cancel a recovery, at least one day
+        delay: u64,
+    },
+    InitiateRecovery {
+        new_owner: Addr,
+    },
+    ApproveRecovery {},
+    CancelRecovery {},
+    ExecuteRecovery {},
+    /// Lets `address` claim the strongbox once the owner has been inactive for
+    /// `inactivity_period` seconds, at least one day
+    SetBeneficiary {
+        address: Addr,
+        inactivity_period: u64,
+        /// Ownership if unset
+        inheritance: Option<Inheritance>,
+    },
+    /// Resets the inactivity timer, like any other message sent by the owner
+    Heartbeat {},
+    ClaimInheritance {},
+    GrantRole {
+        address: Addr,
+        role: Role,
+    },
+    RevokeRole {
+        address: Addr,
+        role: Role,
+    },
+    /// Hands ownership to the contract itself, so owner messages only run once
+    /// `threshold` of the admins approved them
+    SetMultisig {
+        admins: Vec<Addr>,
+        threshold: u32,
+        /// Seconds a proposal stays open, at most 30 days
+        proposal_lifetime: u64,
+    },
+    /// Proposes an owner message, executed as soon as enough admins approved it
+    Propose {
+        action: Box<ExecuteMsg>,
+    },
+    Approve {
+        proposal_id: u64,
+    },
+    /// Creates a vault owned by the sender in multi-tenant mode, or a strongbox contract
+    /// owned by the sender in factory mode
+    CreateStrongbox {},
+    /// Sends `msg` to the vault created by `vault` instead of the sender's own vault
+    WithVault {
+        vault: Addr,
+        msg: Box<ExecuteMsg>,
+    },
 }
 
+// In multi-tenant mode `vault` selects the vault created by that address,
+// queries without it read the instance vault
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    // GetStrongbox returns the current strongbox
-    GetStrongbox { behalf: Addr, key: String },
+    // GetStrongbox returns the current strongbox, encrypted if `encrypt_to` is set
+    GetStrongbox {
+        behalf: Addr,
+        key: String,
+        vault: Option<Addr>,
+        encrypt_to: Option<Binary>,
+    },
+    // GetEntry returns the value stored under a single label
+    GetEntry {
+        behalf: Addr,
+        key: String,
+        vault: Option<Addr>,
+        label: String,
+    },
+    // ListEntryLabels returns the labels of every stored entry
+    ListEntryLabels {
+        behalf: Addr,
+        key: String,
+        vault: Option<Addr>,
+    },
+    // GetPendingOwner returns the address ownership was proposed to, if any
+    GetPendingOwner {
+        behalf: Addr,
+        key: String,
+        vault: Option<Addr>,
+    },
+    // GetStrongboxRevision returns the strongbox as it was at the given revision
+    GetStrongboxRevision {
+        behalf: Addr,
+        key: String,
+        vault: Option<Addr>,
+        revision: u64,
+    },
+    // ListRevisions returns the revisions still kept in the history, newest first
+    ListRevisions {
+        behalf: Addr,
+        key: String,
+        vault: Option<Addr>,
+        page: Option<u32>,
+        page_size: Option<u32>,
+    },
+    // GetRecovery returns the ownership recovery in progress, if any
+    GetRecovery {
+        behalf: Addr,
+        key: String,
+        vault: Option<Addr>,
+    },
+    // GetGuardians returns the guardians who can recover the strongbox, if any
+    GetGuardians {
+        behalf: Addr,
+        key: String,
+        vault: Option<Addr>,
+    },
+    // ListViewers returns every address holding a viewing key, only available to the owner
+    ListViewers {
+        behalf: Addr,
+        key: String,
+        vault: Option<Addr>,
+        page: Option<u32>,
+        page_size: Option<u32>,
+    },
+    // ListRoles returns every address holding a role, only available to the owner
+    ListRoles {
+        behalf: Addr,
+        key: String,
+        vault: Option<Addr>,
+        page: Option<u32>,
+        page_size: Option<u32>,
+    },
+    // WithPermit authenticates the query with a SNIP-24 permit instead of a viewing key
+    WithPermit {
+        permit: Permit<StrongboxPermission>,
+        query: QueryWithPermit,
+        vault: Option<Addr>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StrongboxPermission {
+    /// Read access to the strongbox and its entries
+    Read,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryWithPermit {
+    GetStrongbox {
+        /// secp256k1 public key to encrypt the `StrongboxResponse` to
+        encrypt_to: Option<Binary>,
+    },
+    GetEntry {
+        label: String,
+    },
+    ListEntryLabels {},
+    GetPendingOwner {},
+    GetStrongboxRevision {
+        revision: u64,
+    },
+    ListRevisions {
+        page: Option<u32>,
+        page_size: Option<u32>,
+    },
+    ListViewers {
+        page: Option<u32>,
+        page_size: Option<u32>,
+    },
+    GetRecovery {},
+    GetGuardians {},
+    ListRoles {
+        page: Option<u32>,
+        page_size: Option<u32>,
+    },
+    // MyStrongboxes returns the strongbox contracts the factory created for the signer
+    MyStrongboxes {},
 }
 
 impl QueryMsg {
-    pub fn get_validation_params(&self) -> (Vec<&Addr>, ViewingKey) {
+    /// Accounts and viewing key to validate, `None` for queries authenticated by a permit
+    pub fn get_validation_params(&self) -> Option<(Vec<&Addr>, ViewingKey)> {
+        match self {
+            Self::GetStrongbox { behalf, key, .. } => Some((vec![behalf], ViewingKey(key.clone()))),
+            Self::GetEntry { behalf, key, .. } => Some((vec![behalf], ViewingKey(key.clone()))),
+            Self::ListEntryLabels { behalf, key, .. } => {
+                Some((vec![behalf], ViewingKey(key.clone())))
+            }
+            Self::GetPendingOwner { behalf, key, .. } => {
+                Some((vec![behalf], ViewingKey(key.clone())))
+            }
+            Self::GetStrongboxRevision { behalf, key, .. } => {
+                Some((vec![behalf], ViewingKey(key.clone())))
+            }
+            Self::ListRevisions { behalf, key, .. } => {
+                Some((vec![behalf], ViewingKey(key.clone())))
+            }
+            Self::GetRecovery { behalf, key, .. } => Some((vec![behalf], ViewingKey(key.clone()))),
+            Self::GetGuardians { behalf, key, .. } => Some((vec![behalf], ViewingKey(key.clone()))),
+            Self::ListViewers { behalf, key, .. } => Some((vec![behalf], ViewingKey(key.clone()))),
+            Self::ListRoles { behalf, key, .. } => Some((vec![behalf], ViewingKey(key.clone()))),
+            Self::WithPermit { .. } => None,
+        }
+    }
+
+    /// Creator of the vault the query reads, the instance vault if unset
+    pub fn get_vault(&self) -> Option<&Addr> {
+        match self {
+            Self::GetStrongbox { vault, .. } => vault.as_ref(),
+            Self::GetEntry { vault, .. } => vault.as_ref(),
+            Self::ListEntryLabels { vault, .. } => vault.as_ref(),
+            Self::GetPendingOwner { vault, .. } => vault.as_ref(),
+            Self::GetStrongboxRevision { vault, .. } => vault.as_ref(),
+            Self::ListRevisions { vault, .. } => vault.as_ref(),
+            Self::GetRecovery { vault, .. } => vault.as_ref(),
+            Self::GetGuardians { vault, .. } => vault.as_ref(),
+            Self::ListViewers { vault, .. } => vault.as_ref(),
+            Self::ListRoles { vault, .. } => vault.as_ref(),
+            Self::WithPermit { vault, .. } => vault.as_ref(),
+        }
+    }
+
+    /// The query to perform once the viewing key has been validated
+    pub fn get_query(&self) -> QueryWithPermit {
         match self {
-            Self::GetStrongbox { behalf, key, .. } => (vec![behalf], ViewingKey(key.clone())),
+            Self::GetStrongbox { encrypt_to, .. } => QueryWithPermit::GetStrongbox {
+                encrypt_to: encrypt_to.clone(),
+            },
+            Self::GetEntry { label, .. } => QueryWithPermit::GetEntry {
+                label: label.clone(),
+            },
+            Self::ListEntryLabels { .. } => QueryWithPermit::ListEntryLabels {},
+            Self::GetPendingOwner { .. } => QueryWithPermit::GetPendingOwner {},
+            Self::GetStrongboxRevision { revision, .. } => QueryWithPermit::GetStrongboxRevision {
+                revision: *revision,
+            },
+            Self::ListRevisions {
+                page, page_size, ..
+            } => QueryWithPermit::ListRevisions {
+                page: *page,
+                page_size: *page_size,
+            },
+            Self::GetRecovery { .. } => QueryWithPermit::GetRecovery {},
+            Self::GetGuardians { .. } => QueryWithPermit::GetGuardians {},
+            Self::ListViewers {
+                page, page_size, ..
+            } => QueryWithPermit::ListViewers {
+                page: *page,
+                page_size: *page_size,
+            },
+            Self::ListRoles {
+                page, page_size, ..
+            } => QueryWithPermit::ListRoles {
+                page: *page,
+                page_size: *page_size,
+            },
+            Self::WithPermit { query, .. } => query.clone(),
         }
     }
 }
 
+/// Data set on the response of key messages, in the shape SNIP-20 wallets expect
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteAnswer {
+    CreateViewingKey(ViewingKeyResponse),
+    SetViewingKey { status: ResponseStatus },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ViewingKeyResponse {
+    pub key: ViewingKey,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseStatus {
+    Success,
+    Failure,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct StrongboxResponse {
     pub strongbox: String,
+    pub revision: u64,
+}
+
+/// A `StrongboxResponse` encrypted with ECIES: the key is agreed on between the ephemeral key
+/// and the requested public key, then the JSON response is sealed with ChaCha20-Poly1305
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EncryptedResponse {
+    /// Compressed secp256k1 public key
+    pub ephemeral_public_key: Binary,
+    pub nonce: Binary,
+    pub ciphertext: Binary,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EntryResponse {
+    pub label: String,
+    pub value: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EntryLabelsResponse {
+    pub labels: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOwnerResponse {
+    pub pending_owner: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RecoveryResponse {
+    pub recovery: Option<RecoveryInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RecoveryInfo {
+    pub new_owner: Addr,
+    pub approvals: Vec<Addr>,
+    /// Block time in seconds from which the recovery can be executed
+    pub executable_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GuardiansResponse {
+    pub guardians: Option<GuardiansInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GuardiansInfo {
+    pub addresses: Vec<Addr>,
+    pub threshold: u32,
+    /// Seconds the owner has to cancel a recovery
+    pub delay: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RevisionResponse {
+    pub revision: u64,
+    pub strongbox: String,
+    pub block_height: u64,
+    pub block_time: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RevisionInfo {
+    pub revision: u64,
+    pub block_height: u64,
+    pub block_time: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RevisionsResponse {
+    pub revisions: Vec<RevisionInfo>,
+    pub total: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ViewerInfo {
+    pub address: Addr,
+    pub label: Option<String>,
+    pub created_height: u64,
+    pub created_time: u64,
+    /// False once the key expired or was invalidated by `RevokeAllViewingKeys`
+    pub active: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ViewersResponse {
+    pub viewers: Vec<ViewerInfo>,
+    pub total: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoleInfo {
+    pub address: Addr,
+    pub roles: Vec<Role>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RolesResponse {
+    pub roles: Vec<RoleInfo>,
+    pub total: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StrongboxesResponse {
+    pub strongboxes: Vec<Addr>,
 }
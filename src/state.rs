@@ -1,29 +1,229 @@
 use schemars::JsonSchema;
-use secret_toolkit_crypto::sha_256;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Binary, CanonicalAddr, Storage};
+use cosmwasm_std::{
+    from_slice, to_vec, Addr, Binary, BlockInfo, CanonicalAddr, StdError, StdResult, Storage,
+};
 use cosmwasm_storage::{
     singleton, singleton_read, PrefixedStorage, ReadonlyPrefixedStorage, ReadonlySingleton,
     Singleton,
 };
 
-use crate::viewing_key::ViewingKey;
+use secret_toolkit_crypto::sha_256;
+
+use crate::viewing_key::{KeyHashAlgorithm, ViewingKey};
 
-pub static INITIAL_SEED_LEN: usize = 32;
-pub static ENTROPY_LEN: usize = 20;
+pub static MIN_SEED_LEN: usize = 32;
+pub static MAX_SEED_LEN: usize = 256;
+pub static MIN_ENTROPY_LEN: usize = 20;
+pub static MAX_ENTROPY_LEN: usize = 256;
+pub static DEFAULT_HISTORY_DEPTH: u32 = 10;
+pub static MAX_HISTORY_DEPTH: u32 = 100;
+pub static DEFAULT_PAGE_SIZE: u32 = 10;
+pub static MAX_PAGE_SIZE: u32 = 50;
+pub static MIN_RECOVERY_DELAY: u64 = 24 * 60 * 60;
+pub static MAX_RECOVERY_DELAY: u64 = 365 * 24 * 60 * 60;
+pub static MIN_INACTIVITY_PERIOD: u64 = 24 * 60 * 60;
+pub static MAX_PROPOSAL_LIFETIME: u64 = 30 * 24 * 60 * 60;
 
 pub static CONFIG_KEY: &[u8] = b"strongbox_config";
+pub static STRONGBOX_KEY: &[u8] = b"strongbox_content";
+pub static CONTRACT_VERSION_KEY: &[u8] = b"strongbox_contract_version";
+pub static MODE_KEY: &[u8] = b"strongbox_mode";
+pub static PREFIX_VAULTS: &[u8] = b"strongbox_vaults";
+pub static FACTORY_KEY: &[u8] = b"strongbox_factory";
+pub static PENDING_INSTANCE_OWNER_KEY: &[u8] = b"strongbox_pending_instance_owner";
+pub static PREFIX_OWNED_STRONGBOXES: &[u8] = b"strongbox_owned";
 pub static PREFIX_VIEWING_KEY: &[u8] = b"strongbox_view_key";
+pub static KEY_HASH_SECRET_KEY: &[u8] = b"strongbox_key_hash_secret";
+pub static PREFIX_VIEWER_GRANTS: &[u8] = b"strongbox_viewer_grant";
+pub static VIEWER_COUNT_KEY: &[u8] = b"strongbox_viewer_count";
+pub static PREFIX_VIEWERS: &[u8] = b"strongbox_viewers";
+pub static PREFIX_VIEWER_POSITIONS: &[u8] = b"strongbox_viewer_position";
+pub static PREFIX_ENTRIES: &[u8] = b"strongbox_entries";
+pub static ENTRY_LABELS_KEY: &[u8] = b"strongbox_entry_labels";
+pub static PREFIX_HISTORY: &[u8] = b"strongbox_history";
+pub static GUARDIANS_KEY: &[u8] = b"strongbox_guardians";
+pub static RECOVERY_KEY: &[u8] = b"strongbox_recovery";
+pub static BENEFICIARY_KEY: &[u8] = b"strongbox_beneficiary";
+pub static OWNER_ACTIVITY_KEY: &[u8] = b"strongbox_owner_activity";
+pub static MULTISIG_KEY: &[u8] = b"strongbox_multisig";
+pub static PROPOSAL_COUNT_KEY: &[u8] = b"strongbox_proposal_count";
+pub static PREFIX_PROPOSALS: &[u8] = b"strongbox_proposals";
+pub static PREFIX_ROLES: &[u8] = b"strongbox_roles";
+pub static ROLE_HOLDERS_KEY: &[u8] = b"strongbox_role_holders";
+pub static PREFIX_ENTROPY_HASHES: &[u8] = b"strongbox_entropy_hash";
+pub static PREFIX_REVOKED_PERMITS: &str = "strongbox_revoked_permits";
 
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
 pub struct State {
+    pub owner: CanonicalAddr,
+    pub serenity_seed: Vec<u8>,
+    /// Address the owner proposed to hand the strongbox over to
+    #[serde(default)]
+    pub pending_owner: Option<CanonicalAddr>,
+    /// Revision number of the current strongbox
+    #[serde(default)]
+    pub revision: u64,
+    /// Oldest revision still kept in the history
+    #[serde(default)]
+    pub history_start: u64,
+    /// Number of prior revisions kept besides the current one
+    #[serde(default = "default_history_depth")]
+    pub history_depth: u32,
+    /// Viewing keys written under an older epoch no longer work
+    #[serde(default)]
+    pub key_epoch: u64,
+    #[serde(default)]
+    pub lockdown: Option<Lockdown>,
+}
+
+fn default_history_depth() -> u32 {
+    DEFAULT_HISTORY_DEPTH
+}
+
+/// Rejects viewer queries while active
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct Lockdown {
+    /// Block time in seconds at which the lockdown lifts by itself
+    pub until: Option<u64>,
+}
+
+impl Lockdown {
+    pub fn is_active(&self, block: &BlockInfo) -> bool {
+        !matches!(self.until, Some(until) if block.time.seconds() >= until)
+    }
+}
+
+/// Layout of `State` as stored by v0.1.0, which did not record a contract version
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct LegacyState {
     pub owner: CanonicalAddr,
     pub strongbox: String,
     pub serenity_seed: Vec<u8>,
     pub entropy_hashes: Vec<Binary>,
 }
 
+/// Fields kept inside `State` before 0.3.0 that now live in their own storage
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct LegacyStateFields {
+    #[serde(default)]
+    pub strongbox: Option<String>,
+    #[serde(default)]
+    pub entropy_hashes: Vec<Binary>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct Revision {
+    pub revision: u64,
+    pub strongbox: String,
+    pub block_height: u64,
+    /// Block time in seconds
+    pub block_time: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct ContractVersion {
+    pub contract: String,
+    pub version: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    /// The instance holds a single strongbox
+    #[default]
+    Single,
+    /// Any address can create its own vault
+    MultiTenant,
+    /// Any address can have a strongbox contract instantiated for it
+    Factory,
+}
+
+pub fn mode(storage: &mut dyn Storage) -> Singleton<Mode> {
+    singleton(storage, MODE_KEY)
+}
+
+pub fn mode_read(storage: &dyn Storage) -> ReadonlySingleton<Mode> {
+    singleton_read(storage, MODE_KEY)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct Factory {
+    pub code_id: u64,
+    pub code_hash: String,
+    /// Number of strongboxes instantiated so far
+    pub created: u64,
+}
+
+pub fn factory(storage: &mut dyn Storage) -> Singleton<Factory> {
+    singleton(storage, FACTORY_KEY)
+}
+
+pub fn factory_read(storage: &dyn Storage) -> ReadonlySingleton<Factory> {
+    singleton_read(storage, FACTORY_KEY)
+}
+
+/// Owner of the strongbox being instantiated, until its reply is handled
+pub fn pending_instance_owner(storage: &mut dyn Storage) -> Singleton<CanonicalAddr> {
+    singleton(storage, PENDING_INSTANCE_OWNER_KEY)
+}
+
+pub fn pending_instance_owner_read(storage: &dyn Storage) -> ReadonlySingleton<CanonicalAddr> {
+    singleton_read(storage, PENDING_INSTANCE_OWNER_KEY)
+}
+
+pub fn read_owned_strongboxes(store: &dyn Storage, owner: &CanonicalAddr) -> StdResult<Vec<Addr>> {
+    let owned_store = ReadonlyPrefixedStorage::new(store, PREFIX_OWNED_STRONGBOXES);
+    owned_store
+        .get(owner.as_slice())
+        .map(|raw| from_slice(&raw))
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+pub fn write_owned_strongboxes(
+    store: &mut dyn Storage,
+    owner: &CanonicalAddr,
+    strongboxes: &[Addr],
+) -> StdResult<()> {
+    let mut owned_store = PrefixedStorage::new(store, PREFIX_OWNED_STRONGBOXES);
+    owned_store.set(owner.as_slice(), &to_vec(strongboxes)?);
+    Ok(())
+}
+
+/// Strongbox a message operates on
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Vault {
+    /// The strongbox created on instantiation
+    Instance,
+    /// A vault created with `CreateStrongbox`, keyed by its creator
+    Tenant(CanonicalAddr),
+}
+
+/// Storage of `vault`. The instance vault keeps its keys unprefixed, so every accessor
+/// below can be used on the returned storage as is.
+pub fn vault_storage<'a>(storage: &'a mut dyn Storage, vault: &Vault) -> PrefixedStorage<'a> {
+    match vault {
+        Vault::Instance => PrefixedStorage::multilevel(storage, &[]),
+        Vault::Tenant(creator) => {
+            PrefixedStorage::multilevel(storage, &[PREFIX_VAULTS, creator.as_slice()])
+        }
+    }
+}
+
+pub fn vault_storage_read<'a>(
+    storage: &'a dyn Storage,
+    vault: &Vault,
+) -> ReadonlyPrefixedStorage<'a> {
+    match vault {
+        Vault::Instance => ReadonlyPrefixedStorage::multilevel(storage, &[]),
+        Vault::Tenant(creator) => {
+            ReadonlyPrefixedStorage::multilevel(storage, &[PREFIX_VAULTS, creator.as_slice()])
+        }
+    }
+}
+
 pub fn config(storage: &mut dyn Storage) -> Singleton<State> {
     singleton(storage, CONFIG_KEY)
 }
@@ -32,17 +232,507 @@ pub fn config_read(storage: &dyn Storage) -> ReadonlySingleton<State> {
     singleton_read(storage, CONFIG_KEY)
 }
 
-pub fn read_viewing_key(store: &dyn Storage, owner: &CanonicalAddr) -> Option<Vec<u8>> {
+/// The strongbox is kept apart from `State` so authorization checks don't load it
+pub fn strongbox_content(storage: &mut dyn Storage) -> Singleton<String> {
+    singleton(storage, STRONGBOX_KEY)
+}
+
+pub fn strongbox_content_read(storage: &dyn Storage) -> ReadonlySingleton<String> {
+    singleton_read(storage, STRONGBOX_KEY)
+}
+
+/// Which parts of the strongbox a viewing key may reveal.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ViewerScope {
+    /// The strongbox and every entry
+    #[default]
+    All,
+    /// Only the entries stored under these labels
+    Entries(Vec<String>),
+}
+
+impl ViewerScope {
+    pub fn allows_strongbox(&self) -> bool {
+        matches!(self, ViewerScope::All)
+    }
+
+    pub fn allows_entry(&self, label: &str) -> bool {
+        match self {
+            ViewerScope::All => true,
+            ViewerScope::Entries(labels) => labels.iter().any(|x| x == label),
+        }
+    }
+}
+
+/// Point after which a viewing key stops working. Either bound may be left unset.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
+pub struct Expiration {
+    /// Block time in seconds
+    pub at_time: Option<u64>,
+    pub at_height: Option<u64>,
+}
+
+impl Expiration {
+    pub fn is_expired(&self, block: &BlockInfo) -> bool {
+        matches!(self.at_time, Some(time) if block.time.seconds() >= time)
+            || matches!(self.at_height, Some(height) if block.height >= height)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct ViewerRecord {
+    pub key_hash: Binary,
+    #[serde(default)]
+    pub algorithm: KeyHashAlgorithm,
+    pub scope: ViewerScope,
+    #[serde(default)]
+    pub expiration: Expiration,
+    /// Key epoch the key was written under
+    #[serde(default)]
+    pub epoch: u64,
+}
+
+pub fn legacy_config_read(storage: &dyn Storage) -> ReadonlySingleton<LegacyState> {
+    singleton_read(storage, CONFIG_KEY)
+}
+
+pub fn legacy_state_fields_read(storage: &dyn Storage) -> ReadonlySingleton<LegacyStateFields> {
+    singleton_read(storage, CONFIG_KEY)
+}
+
+pub fn contract_version(storage: &mut dyn Storage) -> Singleton<ContractVersion> {
+    singleton(storage, CONTRACT_VERSION_KEY)
+}
+
+pub fn contract_version_read(storage: &dyn Storage) -> ReadonlySingleton<ContractVersion> {
+    singleton_read(storage, CONTRACT_VERSION_KEY)
+}
+
+/// Permission granted by the owner for a viewer to set up their own viewing key
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct ViewerGrant {
+    pub scope: ViewerScope,
+    pub expiration: Expiration,
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Key epoch the grant was made under
+    #[serde(default)]
+    pub epoch: u64,
+}
+
+pub fn read_viewer_grant(
+    store: &dyn Storage,
+    viewer: &CanonicalAddr,
+) -> StdResult<Option<ViewerGrant>> {
+    let grant_store = ReadonlyPrefixedStorage::new(store, PREFIX_VIEWER_GRANTS);
+    grant_store
+        .get(viewer.as_slice())
+        .map(|raw| from_slice(&raw))
+        .transpose()
+}
+
+pub fn write_viewer_grant(
+    store: &mut dyn Storage,
+    viewer: &CanonicalAddr,
+    grant: &ViewerGrant,
+) -> StdResult<()> {
+    let mut grant_store = PrefixedStorage::new(store, PREFIX_VIEWER_GRANTS);
+    grant_store.set(viewer.as_slice(), &to_vec(grant)?);
+    Ok(())
+}
+
+pub fn remove_viewer_grant(store: &mut dyn Storage, viewer: &CanonicalAddr) {
+    let mut grant_store = PrefixedStorage::new(store, PREFIX_VIEWER_GRANTS);
+    grant_store.remove(viewer.as_slice());
+}
+
+pub fn read_viewing_key(store: &dyn Storage, owner: &CanonicalAddr) -> Option<ViewerRecord> {
     let user_key_store = ReadonlyPrefixedStorage::new(store, PREFIX_VIEWING_KEY);
-    user_key_store.get(owner.as_slice())
+    let raw = user_key_store.get(owner.as_slice())?;
+
+    // Keys written before scopes existed were stored as the bare hash and grant full access
+    Some(from_slice(&raw).unwrap_or(ViewerRecord {
+        key_hash: Binary(raw),
+        algorithm: KeyHashAlgorithm::Sha256,
+        scope: ViewerScope::All,
+        expiration: Expiration::default(),
+        epoch: 0,
+    }))
 }
 
-pub fn write_viewing_key(store: &mut dyn Storage, owner: &CanonicalAddr, key: &ViewingKey) {
+/// Secret keying the viewing key hashes. It is derived from the seed the first time a key is
+/// stored and kept apart from it, so rotating the seed leaves stored keys valid.
+pub fn key_hash_secret(store: &mut dyn Storage) -> StdResult<Vec<u8>> {
+    if let Some(secret) = key_hash_secret_read(store)? {
+        return Ok(secret);
+    }
+
+    let state = config_read(store).load()?;
+    let secret = sha_256(&[state.serenity_seed.as_slice(), KEY_HASH_SECRET_KEY].concat()).to_vec();
+    singleton(store, KEY_HASH_SECRET_KEY).save(&secret)?;
+    Ok(secret)
+}
+
+pub fn key_hash_secret_read(store: &dyn Storage) -> StdResult<Option<Vec<u8>>> {
+    singleton_read(store, KEY_HASH_SECRET_KEY).may_load()
+}
+
+pub fn write_viewing_key(
+    store: &mut dyn Storage,
+    contract: &CanonicalAddr,
+    owner: &CanonicalAddr,
+    key: &ViewingKey,
+    scope: ViewerScope,
+    expiration: Expiration,
+    epoch: u64,
+) -> StdResult<()> {
+    let secret = key_hash_secret(store)?;
+    let algorithm = KeyHashAlgorithm::HmacSha256;
+    let record = ViewerRecord {
+        key_hash: Binary(key.hash(algorithm, &secret, contract, owner).to_vec()),
+        algorithm,
+        scope,
+        expiration,
+        epoch,
+    };
+
     let mut user_key_store = PrefixedStorage::new(store, PREFIX_VIEWING_KEY);
-    user_key_store.set(owner.as_slice(), &sha_256(key.as_bytes()));
+    user_key_store.set(owner.as_slice(), &to_vec(&record)?);
+    Ok(())
 }
 
 pub fn revoke_viewing_key(store: &mut dyn Storage, owner: &CanonicalAddr) {
     let mut user_key_store = PrefixedStorage::new(store, PREFIX_VIEWING_KEY);
     user_key_store.remove(owner.as_slice());
 }
+
+/// Entry of the viewer index, which lists every address holding a viewing key
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct Viewer {
+    pub address: CanonicalAddr,
+    pub label: Option<String>,
+    pub created_height: u64,
+    /// Block time in seconds
+    pub created_time: u64,
+}
+
+pub fn viewer_count(storage: &mut dyn Storage) -> Singleton<u32> {
+    singleton(storage, VIEWER_COUNT_KEY)
+}
+
+pub fn viewer_count_read(storage: &dyn Storage) -> ReadonlySingleton<u32> {
+    singleton_read(storage, VIEWER_COUNT_KEY)
+}
+
+pub fn read_viewer(store: &dyn Storage, position: u32) -> StdResult<Option<Viewer>> {
+    let viewer_store = ReadonlyPrefixedStorage::new(store, PREFIX_VIEWERS);
+    viewer_store
+        .get(&position.to_be_bytes())
+        .map(|raw| from_slice(&raw))
+        .transpose()
+}
+
+fn read_viewer_position(store: &dyn Storage, address: &CanonicalAddr) -> StdResult<Option<u32>> {
+    let position_store = ReadonlyPrefixedStorage::new(store, PREFIX_VIEWER_POSITIONS);
+    position_store
+        .get(address.as_slice())
+        .map(|raw| from_slice(&raw))
+        .transpose()
+}
+
+fn write_viewer_at(store: &mut dyn Storage, position: u32, viewer: &Viewer) -> StdResult<()> {
+    let mut viewer_store = PrefixedStorage::new(store, PREFIX_VIEWERS);
+    viewer_store.set(&position.to_be_bytes(), &to_vec(viewer)?);
+
+    let mut position_store = PrefixedStorage::new(store, PREFIX_VIEWER_POSITIONS);
+    position_store.set(viewer.address.as_slice(), &to_vec(&position)?);
+    Ok(())
+}
+
+/// Appends `viewer` to the index, or replaces its entry if the address is already listed.
+pub fn index_viewer(store: &mut dyn Storage, viewer: &Viewer) -> StdResult<()> {
+    let position = match read_viewer_position(store, &viewer.address)? {
+        Some(position) => position,
+        None => {
+            let count = viewer_count_read(store).may_load()?.unwrap_or_default();
+            viewer_count(store).save(&(count + 1))?;
+            count
+        }
+    };
+
+    write_viewer_at(store, position, viewer)
+}
+
+/// Drops `address` from the index by moving the last entry into its position.
+pub fn unindex_viewer(store: &mut dyn Storage, address: &CanonicalAddr) -> StdResult<()> {
+    let position = match read_viewer_position(store, address)? {
+        Some(position) => position,
+        None => return Ok(()),
+    };
+
+    let last = viewer_count_read(store).load()? - 1;
+    if position != last {
+        let moved = read_viewer(store, last)?.ok_or_else(|| StdError::not_found("Viewer"))?;
+        write_viewer_at(store, position, &moved)?;
+    }
+
+    let mut viewer_store = PrefixedStorage::new(store, PREFIX_VIEWERS);
+    viewer_store.remove(&last.to_be_bytes());
+
+    let mut position_store = PrefixedStorage::new(store, PREFIX_VIEWER_POSITIONS);
+    position_store.remove(address.as_slice());
+
+    viewer_count(store).save(&last)
+}
+
+/// Addresses allowed to hand the strongbox over to a new owner if the owner loses their key
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct Guardians {
+    pub addresses: Vec<CanonicalAddr>,
+    /// Number of guardian approvals a recovery needs
+    pub threshold: u32,
+    /// Seconds the owner has to cancel a recovery before it can be executed
+    pub delay: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct Recovery {
+    pub new_owner: CanonicalAddr,
+    pub approvals: Vec<CanonicalAddr>,
+    /// Block time in seconds
+    pub initiated_at: u64,
+}
+
+pub fn guardians(storage: &mut dyn Storage) -> Singleton<Guardians> {
+    singleton(storage, GUARDIANS_KEY)
+}
+
+pub fn guardians_read(storage: &dyn Storage) -> ReadonlySingleton<Guardians> {
+    singleton_read(storage, GUARDIANS_KEY)
+}
+
+pub fn recovery(storage: &mut dyn Storage) -> Singleton<Recovery> {
+    singleton(storage, RECOVERY_KEY)
+}
+
+pub fn recovery_read(storage: &dyn Storage) -> ReadonlySingleton<Recovery> {
+    singleton_read(storage, RECOVERY_KEY)
+}
+
+/// What the beneficiary receives when claiming the strongbox
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Inheritance {
+    /// Ownership of the strongbox
+    #[default]
+    Ownership,
+    /// A viewing key reading the whole strongbox
+    Viewer,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct Beneficiary {
+    pub address: CanonicalAddr,
+    /// Seconds without owner activity after which the strongbox can be claimed
+    pub inactivity_period: u64,
+    pub inheritance: Inheritance,
+}
+
+/// Last block the owner executed a message in
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct Activity {
+    pub height: u64,
+    /// Block time in seconds
+    pub time: u64,
+}
+
+pub fn beneficiary(storage: &mut dyn Storage) -> Singleton<Beneficiary> {
+    singleton(storage, BENEFICIARY_KEY)
+}
+
+pub fn beneficiary_read(storage: &dyn Storage) -> ReadonlySingleton<Beneficiary> {
+    singleton_read(storage, BENEFICIARY_KEY)
+}
+
+pub fn owner_activity(storage: &mut dyn Storage) -> Singleton<Activity> {
+    singleton(storage, OWNER_ACTIVITY_KEY)
+}
+
+pub fn owner_activity_read(storage: &dyn Storage) -> ReadonlySingleton<Activity> {
+    singleton_read(storage, OWNER_ACTIVITY_KEY)
+}
+
+/// Admins administering the strongbox together once it is owned by the contract itself
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct Multisig {
+    pub admins: Vec<CanonicalAddr>,
+    /// Number of admin approvals a proposal needs
+    pub threshold: u32,
+    /// Seconds a proposal stays open
+    pub proposal_lifetime: u64,
+    /// Id of the first proposal made to these admins, earlier ones can no longer be approved
+    pub first_proposal: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct Proposal {
+    /// Serialized owner message executed once approved
+    pub action: Binary,
+    pub approvals: Vec<CanonicalAddr>,
+    /// Block time in seconds
+    pub expires_at: u64,
+}
+
+pub fn multisig(storage: &mut dyn Storage) -> Singleton<Multisig> {
+    singleton(storage, MULTISIG_KEY)
+}
+
+pub fn multisig_read(storage: &dyn Storage) -> ReadonlySingleton<Multisig> {
+    singleton_read(storage, MULTISIG_KEY)
+}
+
+pub fn proposal_count(storage: &mut dyn Storage) -> Singleton<u64> {
+    singleton(storage, PROPOSAL_COUNT_KEY)
+}
+
+pub fn proposal_count_read(storage: &dyn Storage) -> ReadonlySingleton<u64> {
+    singleton_read(storage, PROPOSAL_COUNT_KEY)
+}
+
+pub fn read_proposal(store: &dyn Storage, id: u64) -> StdResult<Option<Proposal>> {
+    let proposal_store = ReadonlyPrefixedStorage::new(store, PREFIX_PROPOSALS);
+    proposal_store
+        .get(&id.to_be_bytes())
+        .map(|raw| from_slice(&raw))
+        .transpose()
+}
+
+pub fn write_proposal(store: &mut dyn Storage, id: u64, proposal: &Proposal) -> StdResult<()> {
+    let mut proposal_store = PrefixedStorage::new(store, PREFIX_PROPOSALS);
+    proposal_store.set(&id.to_be_bytes(), &to_vec(proposal)?);
+    Ok(())
+}
+
+pub fn remove_proposal(store: &mut dyn Storage, id: u64) {
+    let mut proposal_store = PrefixedStorage::new(store, PREFIX_PROPOSALS);
+    proposal_store.remove(&id.to_be_bytes());
+}
+
+/// Owner permission delegated to another address. The owner holds every role.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Holds every other role, grants and revokes roles
+    Admin,
+    /// Updates the strongbox and its entries
+    Writer,
+    /// Creates and revokes viewing keys
+    KeyManager,
+}
+
+pub fn read_roles(store: &dyn Storage, account: &CanonicalAddr) -> StdResult<Vec<Role>> {
+    let role_store = ReadonlyPrefixedStorage::new(store, PREFIX_ROLES);
+    role_store
+        .get(account.as_slice())
+        .map(|raw| from_slice(&raw))
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+pub fn write_roles(
+    store: &mut dyn Storage,
+    account: &CanonicalAddr,
+    roles: &[Role],
+) -> StdResult<()> {
+    let mut holders = role_holders_read(store).may_load()?.unwrap_or_default();
+
+    let mut role_store = PrefixedStorage::new(store, PREFIX_ROLES);
+    if roles.is_empty() {
+        role_store.remove(account.as_slice());
+        holders.retain(|holder| holder != account);
+    } else {
+        role_store.set(account.as_slice(), &to_vec(roles)?);
+        if !holders.contains(account) {
+            holders.push(account.clone());
+        }
+    }
+
+    role_holders(store).save(&holders)
+}
+
+/// Takes every role back
+pub fn clear_roles(store: &mut dyn Storage) -> StdResult<()> {
+    let holders = role_holders_read(store).may_load()?.unwrap_or_default();
+
+    let mut role_store = PrefixedStorage::new(store, PREFIX_ROLES);
+    for holder in holders {
+        role_store.remove(holder.as_slice());
+    }
+
+    role_holders(store).remove();
+    Ok(())
+}
+
+/// Addresses holding at least one role, in the order they were first granted one
+pub fn role_holders(storage: &mut dyn Storage) -> Singleton<Vec<CanonicalAddr>> {
+    singleton(storage, ROLE_HOLDERS_KEY)
+}
+
+pub fn role_holders_read(storage: &dyn Storage) -> ReadonlySingleton<Vec<CanonicalAddr>> {
+    singleton_read(storage, ROLE_HOLDERS_KEY)
+}
+
+pub fn has_entropy_hash(store: &dyn Storage, hash: &[u8]) -> bool {
+    let entropy_store = ReadonlyPrefixedStorage::new(store, PREFIX_ENTROPY_HASHES);
+    entropy_store.get(hash).is_some()
+}
+
+pub fn write_entropy_hash(store: &mut dyn Storage, hash: &[u8]) {
+    let mut entropy_store = PrefixedStorage::new(store, PREFIX_ENTROPY_HASHES);
+    entropy_store.set(hash, &[1]);
+}
+
+pub fn entry_labels(storage: &mut dyn Storage) -> Singleton<Vec<String>> {
+    singleton(storage, ENTRY_LABELS_KEY)
+}
+
+pub fn entry_labels_read(storage: &dyn Storage) -> ReadonlySingleton<Vec<String>> {
+    singleton_read(storage, ENTRY_LABELS_KEY)
+}
+
+pub fn read_entry(store: &dyn Storage, label: &str) -> Option<String> {
+    let entry_store = ReadonlyPrefixedStorage::new(store, PREFIX_ENTRIES);
+    entry_store
+        .get(label.as_bytes())
+        .map(|value| String::from_utf8_lossy(&value).into_owned())
+}
+
+pub fn write_entry(store: &mut dyn Storage, label: &str, value: &str) {
+    let mut entry_store = PrefixedStorage::new(store, PREFIX_ENTRIES);
+    entry_store.set(label.as_bytes(), value.as_bytes());
+}
+
+pub fn remove_entry(store: &mut dyn Storage, label: &str) {
+    let mut entry_store = PrefixedStorage::new(store, PREFIX_ENTRIES);
+    entry_store.remove(label.as_bytes());
+}
+
+pub fn read_revision(store: &dyn Storage, revision: u64) -> StdResult<Option<Revision>> {
+    let history_store = ReadonlyPrefixedStorage::new(store, PREFIX_HISTORY);
+    history_store
+        .get(&revision.to_be_bytes())
+        .map(|raw| from_slice(&raw))
+        .transpose()
+}
+
+pub fn write_revision(store: &mut dyn Storage, revision: &Revision) -> StdResult<()> {
+    let mut history_store = PrefixedStorage::new(store, PREFIX_HISTORY);
+    history_store.set(&revision.revision.to_be_bytes(), &to_vec(revision)?);
+    Ok(())
+}
+
+pub fn remove_revision(store: &mut dyn Storage, revision: u64) {
+    let mut history_store = PrefixedStorage::new(store, PREFIX_HISTORY);
+    history_store.remove(&revision.to_be_bytes());
+}
@@ -6,30 +6,107 @@ use secret_toolkit_crypto::{sha_256, Prng, SHA256_HASH_SIZE};
 use serde::{Deserialize, Serialize};
 use subtle::ConstantTimeEq;
 
-use cosmwasm_std::{CanonicalAddr, Env};
+use cosmwasm_std::{Binary, CanonicalAddr, Env};
+
+/// Prefix of the viewing keys minted by secret-toolkit, which wallets expect
+pub const VIEWING_KEY_PREFIX: &str = "api_key_";
 
 pub const VIEWING_KEY_SIZE: usize = SHA256_HASH_SIZE;
-pub const VIEWING_KEY_PREFIX: &str = "strongbox_key_";
+
+const HMAC_BLOCK_SIZE: usize = 64;
 
 fn ct_slice_compare(s1: &[u8], s2: &[u8]) -> bool {
     bool::from(s1.ct_eq(s2))
 }
 
+/// HMAC-SHA256 of `message` under `key`, as in RFC 2104
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; SHA256_HASH_SIZE] {
+    let mut block = [0u8; HMAC_BLOCK_SIZE];
+    if key.len() > HMAC_BLOCK_SIZE {
+        block[..SHA256_HASH_SIZE].copy_from_slice(&sha_256(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let inner_pad: Vec<u8> = block.iter().map(|byte| byte ^ 0x36).collect();
+    let outer_pad: Vec<u8> = block.iter().map(|byte| byte ^ 0x5c).collect();
+    let inner_hash = sha_256(&[inner_pad.as_slice(), message].concat());
+    sha_256(&[outer_pad.as_slice(), inner_hash.as_slice()].concat())
+}
+
+/// How the stored hash of a viewing key was computed
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyHashAlgorithm {
+    /// Bare hash of the key, written before 0.4.0
+    #[default]
+    Sha256,
+    /// HMAC of the key bound to the contract and the viewer's address, keyed with a secret
+    /// of the contract so that weak keys can't be guessed from the stored hash
+    HmacSha256,
+}
+
+impl KeyHashAlgorithm {
+    fn id(&self) -> u8 {
+        match self {
+            KeyHashAlgorithm::Sha256 => 0,
+            KeyHashAlgorithm::HmacSha256 => 1,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
 pub struct ViewingKey(pub String);
 
 impl ViewingKey {
-    pub fn check_viewing_key(&self, hashed_pw: &[u8]) -> bool {
-        let mine_hashed = sha_256(&self.as_bytes());
+    /// Keys minted before 0.4.0 start with `strongbox_key_` instead of the toolkit prefix.
+    /// Only the hash of a key is stored, so they keep working as is.
+    pub fn check_viewing_key(
+        &self,
+        hashed_pw: &[u8],
+        algorithm: KeyHashAlgorithm,
+        secret: &[u8],
+        contract: &CanonicalAddr,
+        viewer: &CanonicalAddr,
+    ) -> bool {
+        let mine_hashed = self.hash(algorithm, secret, contract, viewer);
         ct_slice_compare(&mine_hashed, hashed_pw)
     }
 
+    pub fn hash(
+        &self,
+        algorithm: KeyHashAlgorithm,
+        secret: &[u8],
+        contract: &CanonicalAddr,
+        viewer: &CanonicalAddr,
+    ) -> [u8; SHA256_HASH_SIZE] {
+        match algorithm {
+            KeyHashAlgorithm::Sha256 => sha_256(self.as_bytes()),
+            KeyHashAlgorithm::HmacSha256 => {
+                // Addresses are length-prefixed so no two (contract, viewer) pairs share an input
+                let input_len = 9 + contract.len() + viewer.len() + self.as_bytes().len();
+                let mut input = Vec::with_capacity(input_len);
+                input.push(algorithm.id());
+                for address in [contract, viewer] {
+                    input.extend_from_slice(&(address.len() as u32).to_be_bytes());
+                    input.extend_from_slice(address.as_slice());
+                }
+                input.extend_from_slice(self.as_bytes());
+
+                hmac_sha256(secret, &input)
+            }
+        }
+    }
+
     pub fn new(env: &Env, sender: &CanonicalAddr, seed: &[u8], entropy: &[u8]) -> Self {
+        let random = env.block.random.as_ref().map_or(&[][..], Binary::as_slice);
+
         // 16 here represents the lengths in bytes of the block height and time.
-        let entropy_len = 16 + sender.len() + entropy.len();
+        let entropy_len = 16 + random.len() + sender.len() + entropy.len();
         let mut rng_entropy = Vec::with_capacity(entropy_len);
         rng_entropy.extend_from_slice(&env.block.height.to_be_bytes());
         rng_entropy.extend_from_slice(&env.block.time.to_string().as_bytes());
+        rng_entropy.extend_from_slice(random);
         rng_entropy.extend_from_slice(&sender.0);
         rng_entropy.extend_from_slice(entropy);
 
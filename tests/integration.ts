@@ -1,5 +1,6 @@
 import axios from "axios";
 import { Wallet, SecretNetworkClient } from "secretjs";
+import { MsgExecuteContractResponse } from "secretjs/dist/protobuf/secret/compute/v1beta1/msg";
 import fs from "fs";
 import assert from "assert";
 
@@ -232,7 +233,6 @@ async function createViewingKeyTx(
         create_viewing_key: {
           viewer,
           entropy: randomString(20),
-          padding: "",
         },
       },
       sent_funds: [],
@@ -244,13 +244,13 @@ async function createViewingKeyTx(
 
   console.log(`Create ViewingKey TX used ${tx.gasUsed} gas`);
 
-  // Trim viewing key
-  const key_str = tx.data.toString().trim();
-  const viewing_key = key_str.substring(2, key_str.length - 1);
-  return viewing_key;
+  // Answer is {"create_viewing_key":{"key":"..."}}
+  const { data } = MsgExecuteContractResponse.decode(tx.data[0]);
+  const answer = JSON.parse(Buffer.from(data).toString("utf8"));
+  return answer.create_viewing_key.key;
 }
 
-async function transferOwnershipTx(
+async function proposeOwnershipTx(
   client: SecretNetworkClient,
   contractHash: string,
   contractAddess: string,
@@ -262,7 +262,7 @@ async function transferOwnershipTx(
       contract_address: contractAddess,
       code_hash: contractHash,
       msg: {
-        transfer_ownership: {
+        propose_ownership: {
           new_owner: owner,
         },
       },
@@ -273,7 +273,30 @@ async function transferOwnershipTx(
     }
   );
 
-  console.log(`Transfer Ownership TX used ${tx.gasUsed} gas`);
+  console.log(`Propose Ownership TX used ${tx.gasUsed} gas`);
+}
+
+async function acceptOwnershipTx(
+  client: SecretNetworkClient,
+  contractHash: string,
+  contractAddess: string
+) {
+  const tx = await client.tx.compute.executeContract(
+    {
+      sender: client.address,
+      contract_address: contractAddess,
+      code_hash: contractHash,
+      msg: {
+        accept_ownership: {},
+      },
+      sent_funds: [],
+    },
+    {
+      gasLimit: 200000,
+    }
+  );
+
+  console.log(`Accept Ownership TX used ${tx.gasUsed} gas`);
 }
 
 async function revokeViewingKeyTx(
@@ -341,18 +364,19 @@ async function test_transfer_ownership(
   let strongbox = "test strongbox #1";
   await updateStrongboxTx(client, contractHash, contractAddress, strongbox);
 
-  // Prepare new admin and transfer ownership
+  // Prepare new admin and propose ownership
   const new_owner = new Wallet();
-  await transferOwnershipTx(
+  await proposeOwnershipTx(
     client,
     contractHash,
     contractAddress,
     new_owner.address
   );
 
-  // Update client
+  // Update client and accept ownership
   const new_client = initializeClient(new_owner);
   await fillUpFromFaucet(new_client, 100_000_000);
+  await acceptOwnershipTx(new_client, contractHash, contractAddress);
 
   // Try to update strongbox with new owner
   strongbox = "test strongbox #2";